@@ -3,7 +3,7 @@ use engine::{
   application::{
     components::{NetworkedPlayerComponent, SelfComponent},
     input::InputsReader,
-    scene::{Collision, Scene},
+    scene::{Collision, PrefabId, Scene},
   },
   systems::{
     browser::{BrowserController, BrowserReceiver},
@@ -11,15 +11,23 @@ use engine::{
   },
   tsify,
   utils::units::Seconds,
-  PlayerId,
+  ConnectionId, PlayerId,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 use crate::shared::components::{
-  Action, ActionTypes, Character, CharacterState, Harvestable, Item, Quantity,
+  Action, CanTier, CanUpgrade, Character, CharacterState, Harvestable, InventoryCommand,
+  InventoryItem, Item, Quantity, ToolType, VendorStock,
 };
-use crate::shared::game_input::{GameInput, InputState};
+use crate::shared::crafting::{Recipe, RecipeId};
+use crate::shared::game_input::{GameInput, InputState, PendingCommands};
+use crate::shared::market::Market;
+use crate::shared::shop::Purchase;
 use crate::shared::state_machine::{GameState, StateMachine};
+use crate::shared::storage::ChestCommand;
+use crate::shared::timeofday::Calendar;
+use crate::shared::trading::{Trade, TradeCommand};
 
 #[derive(Debug, Serialize, Deserialize, tsify::Tsify)]
 pub enum Message {
@@ -32,14 +40,108 @@ pub enum Message {
   FinishInvitation,
 
   StartSale,
+  CompleteSale {
+    items: Vec<InventoryItem>,
+    cash: u64,
+  },
   FinishSignup,
+
+  SelectSlot {
+    slot: usize,
+  },
+  SwapSlots {
+    from: usize,
+    to: usize,
+  },
+  MoveSlot {
+    from: usize,
+    to: usize,
+  },
+  SplitSlot {
+    slot: usize,
+    quantity: usize,
+  },
+  DiscardSlot {
+    slot: usize,
+  },
+
+  ProposeTrade {
+    partner: ConnectionId,
+  },
+  OfferTrade {
+    items: Vec<InventoryItem>,
+    cash: u64,
+  },
+  ConfirmTrade,
+  CancelTrade,
+  UpdateTrade {
+    trade: Trade,
+  },
+
+  OpenShop {
+    vendor: PrefabId,
+    stock: Vec<VendorStock>,
+  },
+  CloseShop,
+  BuyItem {
+    vendor: PrefabId,
+    item: Item,
+    quantity: usize,
+  },
+
+  OpenCrafting {
+    recipes: HashMap<RecipeId, Recipe>,
+  },
+  CloseCrafting,
+  Craft {
+    recipe: RecipeId,
+  },
+  ToolBroken {
+    tool: ToolType,
+  },
+
+  OpenRepairStation {
+    station: PrefabId,
+    tier: CanTier,
+    upgrade: Option<CanUpgrade>,
+  },
+  CloseRepairStation,
+  UpgradeWateringCan {
+    station: PrefabId,
+  },
+
+  OpenChest {
+    chest: PrefabId,
+    slots: Vec<InventoryItem>,
+  },
+  CloseChest,
+  DepositToChest {
+    chest: PrefabId,
+    slot: usize,
+    quantity: usize,
+  },
+  WithdrawFromChest {
+    chest: PrefabId,
+    slot: usize,
+    quantity: usize,
+  },
+
   UpdateStateMachine {
     state: StateMachine,
   },
+  UpdateMarket {
+    market: Market,
+  },
+  UpdateCalendar {
+    calendar: Calendar,
+  },
   UpdateCharacter {
     character: Character,
     state: String,
   },
+  UpdateInteraction {
+    prompt: Option<String>,
+  },
   UpdateDownloadStats {
     pending_required: usize,
     pending_priority: usize,
@@ -73,6 +175,7 @@ impl BrowserSystem {
   }
 
   pub fn handle_browser_messages(&self, scene: &mut Scene, backpack: &mut Backpack) -> Option<()> {
+    let mut pending = PendingCommands::default();
     let machine = backpack.get_mut::<StateMachine>()?;
     for message in self.receiver.receive() {
       match message {
@@ -86,10 +189,92 @@ impl BrowserSystem {
             machine.start_game();
           }
         }
+        Message::SelectSlot { slot } => {
+          pending.inventory.push(InventoryCommand::Select { slot });
+        }
+        Message::SwapSlots { from, to } => {
+          pending.inventory.push(InventoryCommand::Swap { from, to });
+        }
+        Message::MoveSlot { from, to } => {
+          pending.inventory.push(InventoryCommand::Move { from, to });
+        }
+        Message::SplitSlot { slot, quantity } => {
+          pending
+            .inventory
+            .push(InventoryCommand::Split { slot, quantity });
+        }
+        Message::DiscardSlot { slot } => {
+          pending.inventory.push(InventoryCommand::Discard { slot });
+        }
+        Message::ProposeTrade { partner } => {
+          pending.trades.push(TradeCommand::Propose { partner });
+        }
+        Message::OfferTrade { items, cash } => {
+          pending.trades.push(TradeCommand::Offer { items, cash });
+        }
+        Message::ConfirmTrade => {
+          pending.trades.push(TradeCommand::Confirm);
+        }
+        Message::CancelTrade => {
+          pending.trades.push(TradeCommand::Cancel);
+        }
+        Message::BuyItem {
+          vendor,
+          item,
+          quantity,
+        } => {
+          pending.purchases.push(Purchase {
+            vendor,
+            item,
+            quantity,
+          });
+        }
+        Message::Craft { recipe } => {
+          pending.crafts.push(recipe);
+        }
+        Message::UpgradeWateringCan { station } => {
+          pending.upgrades.push(station);
+        }
+        Message::DepositToChest {
+          chest,
+          slot,
+          quantity,
+        } => {
+          pending.chests.push(ChestCommand::Deposit {
+            chest,
+            slot,
+            quantity,
+          });
+        }
+        Message::WithdrawFromChest {
+          chest,
+          slot,
+          quantity,
+        } => {
+          pending.chests.push(ChestCommand::Withdraw {
+            chest,
+            slot,
+            quantity,
+          });
+        }
         _ => {}
       }
     }
 
+    match backpack.get_mut::<PendingCommands>() {
+      Some(queued) => {
+        queued.inventory.extend(pending.inventory);
+        queued.trades.extend(pending.trades);
+        queued.purchases.extend(pending.purchases);
+        queued.crafts.extend(pending.crafts);
+        queued.chests.extend(pending.chests);
+        queued.upgrades.extend(pending.upgrades);
+      }
+      None => {
+        backpack.insert(pending);
+      }
+    }
+
     Some(())
   }
 
@@ -115,7 +300,7 @@ impl BrowserSystem {
       &Collision<Action, Harvestable>,
     )>() {
       if input.check(InputState::Action)
-        && let ActionTypes::Harvest = character.action
+        && character.action.is("Harvest")
         && let CharacterState::Normal | CharacterState::Running = state
       {
         //self.controller.send(Message::StartSale);
@@ -3,8 +3,9 @@ mod camera;
 
 use crate::planners::{idling, life, social};
 use crate::shared::{
-  animations, collision, components, game_input::GameInput, inputs, loading, pickups,
-  state_machine, timeofday, ui_components,
+  animations, catalog, chopping, collision, components, crafting, farm_actions, farming,
+  game_input::GameInput, inputs, inventory, loading, market, mining, pickups, placement, shop,
+  sprinklers, state_machine, storage, timeofday, tools, trading, ui_components,
 };
 use engine::application::bus::BrowserBus;
 use engine::systems::browser::BrowserActor;
@@ -62,10 +63,25 @@ pub fn main(
   scheduler.attach_registry::<idling::IdleRegistry>();
   scheduler.attach_registry::<life::LifeRegistry>();
   scheduler.attach_registry::<social::SocialRegistry>();
+  scheduler.attach_registry::<farm_actions::FarmActions>();
   scheduler.attach_system::<loading::LoadingSystem>();
+  scheduler.attach_system::<catalog::CatalogSystem>();
   scheduler.attach_system::<inputs::InputsSystem>();
+  scheduler.attach_system::<inventory::InventorySystem>();
   scheduler.attach_system::<timeofday::TimeOfDaySystem>();
   scheduler.attach_system::<pickups::PickupsSystem>();
+  // After the pickups, so a new day records its payouts before prices recover
+  scheduler.attach_system::<market::MarketSystem>();
+  scheduler.attach_system::<farming::FarmingSystem>();
+  scheduler.attach_system::<placement::PlacementSystem>();
+  scheduler.attach_system::<sprinklers::SprinklerSystem>();
+  scheduler.attach_system::<tools::ToolsSystem>();
+  scheduler.attach_system::<mining::MiningSystem>();
+  scheduler.attach_system::<chopping::ChoppingSystem>();
+  scheduler.attach_system::<trading::TradingSystem>();
+  scheduler.attach_system::<shop::ShopSystem>();
+  scheduler.attach_system::<storage::StorageSystem>();
+  scheduler.attach_system::<crafting::CraftingSystem>();
   scheduler.attach_system::<state_machine::StateMachineSystem>();
   //scheduler.attach_system::<tileset::TilesetSystem>();
   scheduler.attach_system::<collision::CollisionSystem>();
@@ -5,12 +5,26 @@ use crate::planners::life;
 use crate::planners::social;
 use crate::shared::collision;
 use crate::shared::components;
+use crate::shared::crafting;
+use crate::shared::farm_actions;
+use crate::shared::farming;
 use crate::shared::state_machine;
 use crate::shared::inputs;
+use crate::shared::inventory;
+use crate::shared::market;
+use crate::shared::mining;
+use crate::shared::chopping;
 use crate::shared::pickups;
+use crate::shared::placement;
+use crate::shared::shop;
+use crate::shared::sprinklers;
+use crate::shared::storage;
 use crate::shared::timeofday;
+use crate::shared::tools;
+use crate::shared::trading;
 use crate::shared::ui_components;
 use crate::shared::animations;
+use crate::shared::catalog::{self, Catalog};
 //use crate::shared::tileset;
 use crate::server::network_controller::NetworkController;
 use crate::shared::game_input::GameInput;
@@ -39,6 +53,11 @@ pub async fn main() {
     format!("{}:{}", address, port).parse().unwrap()
   };
 
+  if let Err(error) = Catalog::load("resources") {
+    log::error!("{:}", error);
+    return;
+  }
+
   let (hdr, _) =
     HdrPipeline::<NetworkController, GameInput>::new("resources", rpc_address, session_address);
   let mut scheduler = Scheduler::new(FRAMES_PER_SECOND);
@@ -49,9 +68,24 @@ pub async fn main() {
   scheduler.attach_registry::<idling::IdleRegistry>();
   scheduler.attach_registry::<life::LifeRegistry>();
   scheduler.attach_registry::<social::SocialRegistry>();
+  scheduler.attach_registry::<farm_actions::FarmActions>();
+  scheduler.attach_system::<catalog::CatalogSystem>();
   scheduler.attach_system::<inputs::InputsSystem>();
+  scheduler.attach_system::<inventory::InventorySystem>();
   scheduler.attach_system::<timeofday::TimeOfDaySystem>();
   scheduler.attach_system::<pickups::PickupsSystem>();
+  // After the pickups, so a new day records its payouts before prices recover
+  scheduler.attach_system::<market::MarketSystem>();
+  scheduler.attach_system::<farming::FarmingSystem>();
+  scheduler.attach_system::<placement::PlacementSystem>();
+  scheduler.attach_system::<sprinklers::SprinklerSystem>();
+  scheduler.attach_system::<tools::ToolsSystem>();
+  scheduler.attach_system::<mining::MiningSystem>();
+  scheduler.attach_system::<chopping::ChoppingSystem>();
+  scheduler.attach_system::<trading::TradingSystem>();
+  scheduler.attach_system::<shop::ShopSystem>();
+  scheduler.attach_system::<storage::StorageSystem>();
+  scheduler.attach_system::<crafting::CraftingSystem>();
   scheduler.attach_system::<state_machine::StateMachineSystem>();
   //scheduler.attach_system::<tileset::TilesetSystem>();
   scheduler.attach_system::<collision::CollisionSystem>();
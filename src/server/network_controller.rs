@@ -1,4 +1,5 @@
 use crate::shared::state_machine::StateMachine;
+use crate::shared::trading::Trades;
 use async_trait::async_trait;
 use engine::systems::network::InternalSender;
 use engine::systems::Backpack;
@@ -136,6 +137,9 @@ impl ChannelEvents for NetworkController {
     if let Some(machine) = backpack.get_mut::<StateMachine>() {
       machine.disconnect(connection_id);
     }
+    if let Some(trades) = backpack.get_mut::<Trades>() {
+      trades.cancel(&connection_id, "The other player left");
+    }
     self.connections.connections.remove(&connection_id);
     backpack.insert::<PlayerConnections>(self.connections.clone());
   }
@@ -0,0 +1,357 @@
+use crate::shared::components::{CropType, FertilizerType, Item, MaterialType, Quality, Stage};
+use crate::shared::crafting::{Recipe, RecipeId};
+use crate::shared::timeofday::Season;
+use engine::{
+  application::scene::Scene,
+  systems::{trusty::MultiplayerController, Backpack, Initializable, Inventory, System},
+  utils::units::Seconds,
+  ConnectionId,
+};
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::shared::state_machine::StateMachine;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::HashSet;
+
+#[cfg(target_arch = "wasm32")]
+use engine::application::components::{NetworkedPlayerComponent, SelfComponent};
+
+// The catalog lives next to the levels in `resources/`. The server loads it at
+// startup and sends it to every client as they join, so they all agree on the
+// same definitions.
+#[cfg(not(target_arch = "wasm32"))]
+const CATALOG_FILE: &str = "catalog.json";
+
+static INSTANCE: RwLock<Option<&'static Catalog>> = RwLock::new(None);
+static EMPTY: OnceLock<Catalog> = OnceLock::new();
+
+#[derive(Debug)]
+pub enum CatalogError {
+  Read(std::io::Error),
+  Parse(serde_json::Error),
+}
+
+impl std::fmt::Display for CatalogError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Read(error) => write!(f, "Could not read the catalog: {:}", error),
+      Self::Parse(error) => write!(f, "The catalog is not valid: {:}", error),
+    }
+  }
+}
+
+impl std::error::Error for CatalogError {}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Catalog {
+  pub crops: HashMap<CropType, CropEntry>,
+  #[serde(default)]
+  pub materials: HashMap<MaterialType, MaterialEntry>,
+  #[serde(default)]
+  pub recipes: HashMap<RecipeId, Recipe>,
+  #[serde(default)]
+  pub fertilizers: HashMap<FertilizerType, FertilizerEntry>,
+}
+
+impl Catalog {
+  /// The catalog in use, which is empty until one is loaded or received from
+  /// the server
+  pub fn get() -> &'static Self {
+    match *INSTANCE.read().unwrap() {
+      Some(catalog) => catalog,
+      None => EMPTY.get_or_init(Catalog::default),
+    }
+  }
+
+  pub fn is_loaded() -> bool {
+    INSTANCE.read().unwrap().is_some()
+  }
+
+  /// Makes `catalog` the one every lookup goes through from now on. Entries
+  /// handed out before stay valid, so the old catalog is never freed.
+  pub fn set(catalog: Catalog) {
+    *INSTANCE.write().unwrap() = Some(Box::leak(Box::new(catalog)));
+  }
+
+  /// Reads the catalog out of the `resources` folder the levels are loaded
+  /// from
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn load(resources: &str) -> Result<(), CatalogError> {
+    let path = std::path::Path::new(resources).join(CATALOG_FILE);
+    let data = std::fs::read_to_string(path).map_err(CatalogError::Read)?;
+    let catalog = serde_json::from_str(&data).map_err(CatalogError::Parse)?;
+    Self::set(catalog);
+    Ok(())
+  }
+
+  pub fn get_crop(&self, crop: &CropType) -> Option<&CropEntry> {
+    self.crops.get(crop)
+  }
+
+  pub fn get_material(&self, material: &MaterialType) -> Option<&MaterialEntry> {
+    self.materials.get(material)
+  }
+
+  pub fn get_recipe(&self, recipe: &RecipeId) -> Option<&Recipe> {
+    self.recipes.get(recipe)
+  }
+
+  pub fn get_fertilizer(&self, fertilizer: &FertilizerType) -> Option<&FertilizerEntry> {
+    self.fertilizers.get(fertilizer)
+  }
+
+  pub fn get_display_name(&self, item: &Item) -> String {
+    match item {
+      Item::Nothing => String::from("Nothing"),
+      Item::Crop(crop, quality) => {
+        let name = match self.get_crop(crop) {
+          Some(entry) => entry.display_name.clone(),
+          None => crop.to_string(),
+        };
+        match quality {
+          Quality::Normal => name,
+          _ => format!("{:} {:}", quality, name),
+        }
+      }
+      Item::Seed(crop) => match self.get_crop(crop) {
+        Some(entry) => format!("{:} Seeds", entry.display_name),
+        None => format!("{:} Seeds", crop),
+      },
+      Item::Material(material) => match self.get_material(material) {
+        Some(entry) => entry.display_name.clone(),
+        None => material.to_string(),
+      },
+      Item::Tool(tool) => tool.to_string(),
+      Item::Fertilizer(fertilizer) => match self.get_fertilizer(fertilizer) {
+        Some(entry) => entry.display_name.clone(),
+        None => fertilizer.to_string(),
+      },
+    }
+  }
+
+  pub fn get_stack_size(&self, item: &Item) -> usize {
+    match item {
+      Item::Nothing => 0,
+      Item::Crop(crop, _) | Item::Seed(crop) => match self.get_crop(crop) {
+        Some(entry) => entry.stack_size,
+        None => 1,
+      },
+      Item::Material(material) => match self.get_material(material) {
+        Some(entry) => entry.stack_size,
+        None => 1,
+      },
+      Item::Tool(_) => 1,
+      Item::Fertilizer(fertilizer) => match self.get_fertilizer(fertilizer) {
+        Some(entry) => entry.stack_size,
+        None => 1,
+      },
+    }
+  }
+
+  pub fn get_placed_prefab(&self, item: &Item) -> Option<&str> {
+    match item {
+      Item::Material(material) => self
+        .get_material(material)
+        .and_then(|entry| entry.places.as_deref()),
+      _ => None,
+    }
+  }
+
+  pub fn get_price(&self, item: &Item) -> Option<u64> {
+    match item {
+      Item::Crop(crop, quality) => self
+        .get_crop(crop)
+        .map(|entry| quality.apply_to_price(entry.sell_price)),
+      Item::Material(material) => self
+        .get_material(material)
+        .and_then(|entry| entry.sell_price),
+      Item::Fertilizer(fertilizer) => self
+        .get_fertilizer(fertilizer)
+        .and_then(|entry| entry.sell_price),
+      Item::Nothing | Item::Seed(_) | Item::Tool(_) => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CropEntry {
+  pub display_name: String,
+  pub prefab: String,
+  pub stages: HashMap<Stage, String>,
+
+  pub seed_timeout: Seconds,
+  pub seedling_timeout: Seconds,
+  pub flowering_timeout: Seconds,
+  pub mature_timeout: Seconds,
+
+  pub award: usize,
+  pub sell_price: u64,
+  pub stack_size: usize,
+  pub season_start: u32,
+  pub season_end: u32,
+
+  // Days in a row without water before a growing crop withers
+  #[serde(default = "CropEntry::default_wither_days")]
+  pub wither_days: u32,
+  // Compost left behind when harvesting the crop after it rotted
+  #[serde(default)]
+  pub compost: usize,
+  // Harvesting leaves the plant behind to flower again instead of clearing it
+  #[serde(default)]
+  pub regrows: bool,
+}
+
+impl CropEntry {
+  fn default_wither_days() -> u32 {
+    3
+  }
+
+  /// Seasons are counted from spring, and a season range that ends before it
+  /// starts wraps around the new year
+  pub fn is_in_season(&self, season: Season) -> bool {
+    let index = season.get_index();
+    match self.season_start <= self.season_end {
+      true => (self.season_start..=self.season_end).contains(&index),
+      false => index >= self.season_start || index <= self.season_end,
+    }
+  }
+
+  pub fn get_stage_prefab(&self, stage: &Stage) -> Option<&str> {
+    self.stages.get(stage).map(|name| name.as_str())
+  }
+
+  pub fn get_timeout(&self, stage: &Stage) -> Option<Seconds> {
+    match stage {
+      Stage::Seeds => Some(self.seed_timeout),
+      Stage::Seedling => Some(self.seedling_timeout),
+      Stage::Flowering => Some(self.flowering_timeout),
+      Stage::Mature => Some(self.mature_timeout),
+      Stage::Display | Stage::Rotten | Stage::Withered => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialEntry {
+  pub display_name: String,
+  pub stack_size: usize,
+  #[serde(default)]
+  pub sell_price: Option<u64>,
+  // Prefab put down on the farm grid when the item is placed
+  #[serde(default)]
+  pub places: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FertilizerEntry {
+  pub display_name: String,
+  pub stack_size: usize,
+  #[serde(default)]
+  pub sell_price: Option<u64>,
+  // Scales the stage timeouts of crops growing on the tile, below 1 is faster
+  #[serde(default = "FertilizerEntry::default_growth")]
+  pub growth: f32,
+  // Chance, between 0 and 1, that a crop finishing its growth on the tile comes
+  // out one quality tier higher
+  #[serde(default)]
+  pub quality_chance: f32,
+  // Tint of fertilized tiles
+  pub color: [f32; 3],
+}
+
+impl FertilizerEntry {
+  fn default_growth() -> f32 {
+    1.0
+  }
+
+  pub fn scale_timeout(&self, timeout: Seconds) -> Seconds {
+    Seconds::new(*timeout * self.growth)
+  }
+
+  /// Rolls against `quality_chance` for whether a crop gets the quality boost
+  pub fn roll_quality(&self) -> bool {
+    self.quality_chance > 0.0 && thread_rng().gen_bool(self.quality_chance.min(1.0) as f64)
+  }
+}
+
+/// The catalog sent to the player who just joined. Everyone else already has
+/// it and leaves it be.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogDelivery {
+  pub connection_id: ConnectionId,
+  pub catalog: Catalog,
+}
+
+/// Hands the catalog the server loaded to clients as they join
+pub struct CatalogSystem {
+  multiplayer: MultiplayerController,
+  // Players the catalog was already sent to
+  #[cfg(not(target_arch = "wasm32"))]
+  connections: HashSet<ConnectionId>,
+}
+
+impl Initializable for CatalogSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let multiplayer = inventory.get::<MultiplayerController>().clone();
+    Self {
+      multiplayer,
+      #[cfg(not(target_arch = "wasm32"))]
+      connections: HashSet::new(),
+    }
+  }
+}
+
+impl CatalogSystem {
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn handle_joins(&mut self, backpack: &mut Backpack) {
+    let connections: HashSet<ConnectionId> = match backpack.get::<StateMachine>() {
+      Some(machine) => machine.players.iter().map(|(id, _, _)| *id).collect(),
+      None => return,
+    };
+
+    for connection_id in &connections {
+      if !self.connections.contains(connection_id) {
+        self.multiplayer.broadcast_custom(CatalogDelivery {
+          connection_id: *connection_id,
+          catalog: Catalog::get().clone(),
+        });
+      }
+    }
+    self.connections = connections;
+  }
+
+  /// Keeps the catalog addressed to the local player. Deliveries wait until
+  /// the player spawned, as only then is it known which one is theirs.
+  #[cfg(target_arch = "wasm32")]
+  pub fn handle_receive_from_server(&mut self, scene: &mut Scene) {
+    let connection_id = match scene.query_one::<(&NetworkedPlayerComponent, &SelfComponent)>() {
+      Some((_, (network, _))) => network.connection_id,
+      None => return,
+    };
+
+    while let Ok(delivery) = self.multiplayer.try_recv_custom::<CatalogDelivery>() {
+      if delivery.connection_id == connection_id {
+        Catalog::set(delivery.catalog);
+      }
+    }
+  }
+}
+
+impl System for CatalogSystem {
+  fn get_name(&self) -> &'static str {
+    "CatalogSystem"
+  }
+
+  // Each side only uses one of the two
+  #[allow(unused_variables)]
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    #[cfg(not(target_arch = "wasm32"))]
+    self.handle_joins(backpack);
+    #[cfg(target_arch = "wasm32")]
+    self.handle_receive_from_server(scene);
+  }
+}
@@ -0,0 +1,202 @@
+use crate::shared::components::{
+  Character, Durability, FarmWork, InventoryError, Item, MaterialType, ToolType, Tree,
+};
+use crate::shared::farming::FarmAction;
+use crate::shared::timeofday::DayCycle;
+use crate::shared::tools::use_tool;
+use engine::{
+  application::{
+    components::ModelComponent,
+    scene::{Scene, TransformComponent},
+  },
+  nalgebra::Vector3,
+  systems::{Backpack, Initializable, Inventory, System},
+  utils::units::Seconds,
+  Entity,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::shared::pickups::spawn_drop;
+
+/// Model of a felled tree, kept on its stump so it grows back into the same
+/// kind of tree
+pub struct Felled {
+  pub model: ModelComponent,
+}
+
+/// Swings the axe the player is holding at a tree, felling it into a stump
+/// once it took enough hits
+#[derive(Default)]
+pub struct Chop {}
+
+impl FarmAction for Chop {
+  fn name(&self) -> &'static str {
+    "Chop"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Chop Tree"
+  }
+
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Chopping...")
+  }
+
+  fn is_selectable(&self) -> bool {
+    false
+  }
+
+  fn get_color(&self) -> Vector3<f32> {
+    Vector3::new(0.0, 1.0, 0.0)
+  }
+
+  fn get_candidates(&self, scene: &mut Scene, player: Entity) -> Vec<Entity> {
+    match scene.get_components_mut::<&Character>(player) {
+      Some(character) if character.is_holding(&Item::Tool(ToolType::Axe)) => {}
+      _ => return vec![],
+    }
+
+    let mut trees = vec![];
+    for (entity, tree) in scene.query_mut::<&Tree>() {
+      if !tree.is_stump() {
+        trees.push(entity);
+      }
+    }
+    trees
+  }
+
+  fn start(
+    &self,
+    scene: &mut Scene,
+    _: &mut Backpack,
+    player: Entity,
+    target: Entity,
+  ) -> Option<FarmWork> {
+    let (character, durability) =
+      scene.get_components_mut::<(&Character, Option<&mut Durability>)>(player)?;
+    if !character.is_holding(&Item::Tool(ToolType::Axe)) {
+      return None;
+    }
+    use_tool(durability, ToolType::Axe).ok()?;
+
+    Some(FarmWork::new(self.name(), target, Seconds::new(1.0)))
+  }
+
+  fn complete(&self, scene: &mut Scene, backpack: &mut Backpack, player: Entity, work: FarmWork) {
+    let tree_entity = match work.get_target() {
+      Some(tree_entity) => tree_entity,
+      None => return,
+    };
+
+    let day = match backpack.get::<DayCycle>() {
+      Some(cycle) => cycle.day,
+      None => 0,
+    };
+
+    let (logs, transform) =
+      match scene.get_components_mut::<(&mut Tree, &TransformComponent)>(tree_entity) {
+        Some((tree, transform)) => {
+          if tree.is_stump() || !tree.hit() {
+            return;
+          }
+          tree.fell(day);
+          (tree.logs, transform.clone())
+        }
+        None => return,
+      };
+
+    let mut overflow = None;
+    if let Some(character) = scene.get_components_mut::<&mut Character>(player) {
+      let item = Item::Material(MaterialType(String::from("Log")));
+      match character.award(item, logs) {
+        Ok(_) => {}
+        // Logs that do not fit in the inventory are left next to the stump
+        Err(InventoryError::Full { item, remaining }) => overflow = Some((item, remaining)),
+        Err(error) => log::warn!("chopping failed: {:}", error),
+      }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some((item, remaining)) = overflow {
+      spawn_drop(scene, item, remaining, &transform);
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = (overflow, transform);
+
+    if let Some(model) = scene
+      .get_components_mut::<&ModelComponent>(tree_entity)
+      .cloned()
+    {
+      scene.add_local_component(tree_entity, Felled { model });
+    }
+
+    if let Some(prefab) = scene.get_parent_prefab_owned("Prefab::Stump")
+      && let Some(model) = prefab.get::<ModelComponent>()
+    {
+      scene.add_component(tree_entity, model.clone());
+    }
+  }
+}
+
+/// Grows stumps back into trees once their day comes
+pub struct ChoppingSystem {}
+
+impl Initializable for ChoppingSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {}
+  }
+}
+
+impl ChoppingSystem {
+  pub fn handle_regrowth(&self, scene: &mut Scene, backpack: &mut Backpack) {
+    let day = match backpack.get::<DayCycle>() {
+      Some(DayCycle {
+        day,
+        is_new_day: true,
+        ..
+      }) => *day,
+      _ => return,
+    };
+
+    let mut regrown = vec![];
+    for (entity, tree) in scene.query_mut::<&mut Tree>() {
+      if let Some(regrows_on) = tree.regrows_on
+        && regrows_on <= day
+      {
+        tree.regrow();
+        regrown.push(entity);
+      }
+    }
+
+    if regrown.is_empty() {
+      return;
+    }
+
+    // Stumps that were not felled here, like those already felled when the
+    // player joined, grow back into the default tree
+    let fallback = scene
+      .get_parent_prefab_owned("Prefab::Tree")
+      .and_then(|prefab| prefab.get::<ModelComponent>().cloned());
+
+    for entity in regrown {
+      let felled = scene
+        .get_components_mut::<&Felled>(entity)
+        .map(|felled| felled.model.clone());
+      let _ = scene.remove_component::<Felled>(entity);
+
+      if let Some(model) = felled.or_else(|| fallback.clone()) {
+        scene.add_component(entity, model);
+      }
+    }
+  }
+}
+
+impl System for ChoppingSystem {
+  fn get_name(&self) -> &'static str {
+    "ChoppingSystem"
+  }
+
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    self.handle_regrowth(scene, backpack);
+  }
+}
@@ -1,3 +1,6 @@
+use crate::shared::catalog::{Catalog, CropEntry, FertilizerEntry, MaterialEntry};
+use crate::shared::crafting::{RecipeId, RecipeItem};
+use crate::shared::timeofday::Calendar;
 use engine::{
   application::scene::{PrefabId, ProvideAssets},
   nalgebra::{Unit, Vector3},
@@ -26,13 +29,19 @@ impl Registry for GameComponents {
     Character::register();
     DisplayItem::register();
     SalesBin::register();
+    Vendor::register();
+    CraftingStation::register();
+    Chest::register();
+    RepairStation::register();
+    Sprinkler::register();
+    Placeable::register();
     WaterCan::register();
     WaterSource::register();
     Harvestable::register();
     Rock::register();
+    Tree::register();
     Durability::register();
     Crop::register();
-    Seeds::register();
     Seat::register();
     TimeOfDay::register();
     Action::register();
@@ -69,7 +78,12 @@ pub struct CameraFollower {
 impl ProvideAssets for CameraFollower {}
 
 #[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
-pub struct Pickup {}
+pub struct Pickup {
+  // Items dropped on the ground are collected into the inventory, while
+  // pickups without one are carried around instead
+  #[serde(default)]
+  pub item: Option<InventoryItem>,
+}
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Registerable, Schema, Duplicate)]
 enum ChangeDirection {
@@ -218,13 +232,68 @@ impl ProvideAssets for Player {}
 pub enum CharacterState {
   Normal,
   Running,
-  CollectingWater,
   ShowingOff { item: Item },
-  WorkingTile(Entity),
-  ThrowingSeed(Entity, Level),
-  Harvesting(Entity, Level),
+  Farming(FarmWork),
+}
+
+/// Work on one of the registered farm actions, from the moment a player starts
+/// it until it is done
+#[derive(Debug, Clone, PartialEq)]
+pub struct FarmWork {
+  pub action: ActionTypes,
+  // What the work is done on, the target the player started it on first
+  pub targets: Vec<Entity>,
+  // Taken from the player when the work started, and used once it is done
+  pub item: Option<Item>,
+  // Recipe being crafted, for work started from a crafting station
+  pub recipe: Option<RecipeId>,
+  pub timing: Level,
+  // Steps grown by holding the action down, for actions that charge up
+  pub charge: Option<u32>,
+}
+
+impl FarmWork {
+  pub fn new(action: &str, target: Entity, duration: Seconds) -> Self {
+    Self {
+      action: ActionTypes::from(action),
+      targets: vec![target],
+      item: None,
+      recipe: None,
+      timing: Level::to_max(1.0, duration),
+      charge: None,
+    }
+  }
+
+  pub fn with_item(self, item: Item) -> Self {
+    Self {
+      item: Some(item),
+      ..self
+    }
+  }
+
+  pub fn with_recipe(self, recipe: RecipeId) -> Self {
+    Self {
+      recipe: Some(recipe),
+      ..self
+    }
+  }
+
+  pub fn get_target(&self) -> Option<Entity> {
+    self.targets.first().copied()
+  }
+}
+
+/// The one thing a player acts on when they press the action button, picked
+/// out of everything in reach by how close it is and whether they face it
+#[derive(Debug, Clone, PartialEq)]
+pub struct InteractionTarget {
+  pub action: ActionTypes,
+  pub entity: Entity,
 }
 
+// Number of inventory slots that can be selected from the hotbar
+pub const HOTBAR_SIZE: usize = 9;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Registerable, Schema, Duplicate)]
 pub struct Character {
   pub cash: u64,
@@ -233,91 +302,283 @@ pub struct Character {
   pub hunger: Level,
   pub action: ActionTypes,
   pub inventory: Vec<InventoryItem>,
+  #[serde(default)]
+  pub selected: usize,
 }
 
 impl Character {
-  pub fn award(&mut self, search: Item, increase: usize) -> Option<bool> {
-    let mut is_new = false;
+  pub fn get_selected(&self) -> Option<&InventoryItem> {
+    self.inventory.get(self.selected)
+  }
+
+  pub fn get_hotbar_size(&self) -> usize {
+    HOTBAR_SIZE.min(self.inventory.len())
+  }
+
+  pub fn select_slot(&mut self, slot: usize) -> Result<(), InventoryError> {
+    if slot >= self.get_hotbar_size() {
+      return Err(InventoryError::InvalidSlot { slot });
+    }
+
+    self.selected = slot;
+    Ok(())
+  }
 
-    if let Some(item) = self
+  pub fn swap_slots(&mut self, from: usize, to: usize) -> Result<(), InventoryError> {
+    self.check_slot(from)?;
+    self.check_slot(to)?;
+
+    self.inventory.swap(from, to);
+    Ok(())
+  }
+
+  /// Moves the slot `from` onto `to`, merging both stacks when they hold the
+  /// same item and swapping them otherwise.
+  pub fn move_slot(&mut self, from: usize, to: usize) -> Result<(), InventoryError> {
+    self.check_slot(from)?;
+    self.check_slot(to)?;
+
+    if from == to {
+      return Ok(());
+    }
+
+    let item = self.inventory[from].item.clone();
+    if item == Item::Nothing || item != self.inventory[to].item {
+      self.inventory.swap(from, to);
+      return Ok(());
+    }
+
+    let available = self.inventory[from].quantity.get_available();
+    let moved = self.inventory[to]
+      .quantity
+      .fill(available, item.get_stack_size());
+    self.inventory[from].quantity.decrement_by(&item, moved)?;
+
+    if let Quantity::Empty = self.inventory[from].quantity {
+      self.inventory[from].clear();
+    }
+
+    Ok(())
+  }
+
+  /// Moves `quantity` out of `slot` into the first empty slot
+  pub fn split_slot(&mut self, slot: usize, quantity: usize) -> Result<(), InventoryError> {
+    self.check_slot(slot)?;
+
+    let item = self.inventory[slot].item.clone();
+    let available = self.inventory[slot].quantity.get_available();
+    if quantity == 0 || quantity >= available {
+      return Err(InventoryError::NotEnough {
+        item,
+        requested: quantity,
+        available,
+      });
+    }
+
+    let empty = match self
       .inventory
-      .iter_mut()
-      .find(|inventory| inventory.item == search)
+      .iter()
+      .position(|inventory| inventory.item == Item::Nothing)
     {
-      item.quantity.increase_by(increase);
-      return Some(false);
+      Some(empty) => empty,
+      None => {
+        return Err(InventoryError::Full {
+          item,
+          remaining: quantity,
+        })
+      }
+    };
+
+    self.inventory[slot]
+      .quantity
+      .decrement_by(&item, quantity)?;
+    self.inventory[empty] = InventoryItem {
+      item,
+      quantity: Quantity::Finite(quantity),
+    };
+
+    Ok(())
+  }
+
+  pub fn take_slot(
+    &mut self,
+    slot: usize,
+    quantity: usize,
+  ) -> Result<InventoryItem, InventoryError> {
+    self.check_slot(slot)?;
+    self.inventory[slot].take(quantity)
+  }
+
+  pub fn discard_slot(&mut self, slot: usize) -> Result<InventoryItem, InventoryError> {
+    self.check_slot(slot)?;
+
+    let discarded = self.inventory[slot].clone();
+    self.inventory[slot].clear();
+    Ok(discarded)
+  }
+
+  pub fn apply(&mut self, command: &InventoryCommand) -> Result<(), InventoryError> {
+    match *command {
+      InventoryCommand::Select { slot } => self.select_slot(slot),
+      InventoryCommand::Swap { from, to } => self.swap_slots(from, to),
+      InventoryCommand::Move { from, to } => self.move_slot(from, to),
+      InventoryCommand::Split { slot, quantity } => self.split_slot(slot, quantity),
+      InventoryCommand::Discard { slot } => self.discard_slot(slot).map(|_| ()),
     }
+  }
 
-    match self
+  fn check_slot(&self, slot: usize) -> Result<(), InventoryError> {
+    match slot < self.inventory.len() {
+      true => Ok(()),
+      false => Err(InventoryError::InvalidSlot { slot }),
+    }
+  }
+
+  /// Adds `increase` of `search`, topping up existing stacks before taking empty
+  /// slots. Returns whether the item is new to the inventory, or how many did not
+  /// fit when it runs out of room.
+  pub fn award(&mut self, search: Item, increase: usize) -> Result<bool, InventoryError> {
+    let is_new = self.count(&search) == 0;
+
+    match stack_into(&mut self.inventory, &search, increase) {
+      0 => Ok(is_new),
+      remaining => Err(InventoryError::Full {
+        item: search,
+        remaining,
+      }),
+    }
+  }
+
+  /// Takes `quantity` of `search` out of the inventory, emptying the slots that
+  /// run out. Nothing is taken unless the whole quantity is available.
+  pub fn remove(&mut self, search: &Item, quantity: usize) -> Result<(), InventoryError> {
+    let available = self.count(search);
+    if available < quantity {
+      return Err(InventoryError::NotEnough {
+        item: search.clone(),
+        requested: quantity,
+        available,
+      });
+    }
+
+    let mut remaining = quantity;
+    for inventory in self
       .inventory
       .iter_mut()
-      .find(|inventory| inventory.item == Item::Nothing)
+      .rev()
+      .filter(|inventory| &inventory.item == search)
     {
-      Some(inventory) if increase == 0 => {
-        inventory.item = search;
-        inventory.quantity = Quantity::Empty;
-
-        Some(true)
+      if remaining == 0 {
+        break;
       }
-      Some(inventory) => {
-        inventory.item = search;
-        inventory.quantity = Quantity::Finite(increase);
 
-        Some(true)
+      let taken = remaining.min(inventory.quantity.get_available());
+      inventory.quantity.decrement_by(search, taken)?;
+      remaining -= taken;
+
+      if let Quantity::Empty = inventory.quantity {
+        inventory.clear();
       }
-      None => None,
     }
+
+    Ok(())
   }
 
-  pub fn can_use(&self, item: &Item) -> bool {
-    match self
-      .inventory
-      .iter()
-      .find(|inventory| &inventory.item == item)
-    {
-      Some(item) => item.quantity.can_use(),
-      None => false,
+  /// Takes every one of `items` out of the inventory, or nothing at all when
+  /// one of them is missing.
+  pub fn remove_all(&mut self, items: &[RecipeItem]) -> Result<(), InventoryError> {
+    let mut next = self.clone();
+    for required in items {
+      next.remove(&required.item, required.quantity)?;
     }
+
+    *self = next;
+    Ok(())
   }
 
-  pub fn has_at_least(&self, item: &Item, quantity: usize) -> bool {
-    match self
+  pub fn count(&self, search: &Item) -> usize {
+    self
       .inventory
       .iter()
-      .find(|inventory| &inventory.item == item)
-    {
-      Some(item) => item.quantity.has_at_least(quantity),
+      .filter(|inventory| &inventory.item == search)
+      .fold(0, |total, inventory| {
+        total.saturating_add(inventory.quantity.get_available())
+      })
+  }
+
+  pub fn can_use(&self, item: &Item) -> bool {
+    self.count(item) > 0
+  }
+
+  pub fn has_at_least(&self, item: &Item, quantity: usize) -> bool {
+    self.count(item) >= quantity
+  }
+
+  pub fn is_holding(&self, item: &Item) -> bool {
+    match self.get_selected() {
+      Some(selected) => selected.item == *item && selected.quantity.can_use(),
       None => false,
     }
   }
 
-  pub fn decrement_by(&mut self, item: &Item, quantity: usize) {
-    if let Some(item) = self
-      .inventory
-      .iter_mut()
-      .find(|inventory| &inventory.item == item)
-    {
-      item.quantity.decrement_by(quantity);
+  /// The seed in the selected slot, if the player is holding any
+  pub fn get_selected_seed(&self) -> Option<CropType> {
+    match self.get_selected() {
+      Some(InventoryItem {
+        item: Item::Seed(crop),
+        quantity,
+      }) if quantity.can_use() => Some(crop.clone()),
+      _ => None,
     }
   }
 
-  pub fn increase_by(&mut self, item: &Item, quantity: usize) {
-    if let Some(item) = self
-      .inventory
-      .iter_mut()
-      .find(|inventory| &inventory.item == item)
-    {
-      item.quantity.increase_by(quantity);
+  /// The fertilizer in the selected slot, if the player is holding any
+  pub fn get_selected_fertilizer(&self) -> Option<FertilizerType> {
+    match self.get_selected() {
+      Some(InventoryItem {
+        item: Item::Fertilizer(fertilizer),
+        quantity,
+      }) if quantity.can_use() => Some(fertilizer.clone()),
+      _ => None,
+    }
+  }
+
+  /// The item in the selected slot and the prefab it places, if the player is
+  /// holding something that can be placed on the farm
+  pub fn get_selected_placeable(&self) -> Option<(Item, &'static str)> {
+    match self.get_selected() {
+      Some(InventoryItem { item, quantity }) if quantity.can_use() => item
+        .get_placed_prefab()
+        .map(|prefab| (item.clone(), prefab)),
+      _ => None,
+    }
+  }
+
+  /// Empties every slot holding a crop that can be sold, returning what was
+  /// taken. Materials and tools stay with the player even when they have a price.
+  pub fn take_sellables(&mut self) -> Vec<InventoryItem> {
+    let mut taken = vec![];
+
+    for inventory in self.inventory.iter_mut() {
+      if let Item::Crop(..) = inventory.item
+        && inventory.item.get_price().is_some()
+        && let Quantity::Finite(1..) = inventory.quantity
+      {
+        taken.push(inventory.clone());
+        inventory.clear();
+      }
     }
+
+    taken
   }
 }
 
 impl ProvideAssets for Character {}
 
+/// Key into the crops of the `Catalog`, e.g. `"Pumpkin"`
 #[derive(
   Debug,
   Clone,
-  Copy,
   Eq,
   PartialEq,
   Hash,
@@ -328,27 +589,82 @@ impl ProvideAssets for Character {}
   Duplicate,
   tsify::Tsify,
 )]
-pub enum CropType {
-  Pumpkin,
-}
+#[serde(transparent)]
+pub struct CropType(pub String);
 
 impl std::fmt::Display for CropType {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    match self {
-      Self::Pumpkin => write!(f, "Pumpkin"),
-    }
+    write!(f, "{:}", self.0)
   }
 }
 
 impl CropType {
-  pub fn get_prefab(&self) -> &'static str {
-    match self {
-      Self::Pumpkin => "Prefab::Pumpkin",
-    }
+  pub fn get_entry(&self) -> Option<&'static CropEntry> {
+    Catalog::get().get_crop(self)
+  }
+}
+
+/// Key into the materials of the `Catalog`, e.g. `"Wood"`
+#[derive(
+  Debug,
+  Clone,
+  Eq,
+  PartialEq,
+  Hash,
+  Serialize,
+  Deserialize,
+  Registerable,
+  Schema,
+  Duplicate,
+  tsify::Tsify,
+)]
+#[serde(transparent)]
+pub struct MaterialType(pub String);
+
+impl std::fmt::Display for MaterialType {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{:}", self.0)
+  }
+}
+
+impl MaterialType {
+  pub fn get_entry(&self) -> Option<&'static MaterialEntry> {
+    Catalog::get().get_material(self)
+  }
+}
+
+/// Key into the fertilizers of the `Catalog`, e.g. `"SpeedGro"`
+#[derive(
+  Debug,
+  Clone,
+  Eq,
+  PartialEq,
+  Hash,
+  Serialize,
+  Deserialize,
+  Registerable,
+  Schema,
+  Duplicate,
+  tsify::Tsify,
+)]
+#[serde(transparent)]
+pub struct FertilizerType(pub String);
+
+impl std::fmt::Display for FertilizerType {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{:}", self.0)
+  }
+}
+
+impl FertilizerType {
+  pub fn get_entry(&self) -> Option<&'static FertilizerEntry> {
+    Catalog::get().get_fertilizer(self)
   }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+#[derive(
+  Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, Registerable, Schema, Duplicate,
+)]
 pub enum Stage {
   Seeds,
   Seedling,
@@ -356,6 +672,10 @@ pub enum Stage {
   //Sprout,
   Mature,
   Display,
+  // Left in the field for too long once mature
+  Rotten,
+  // Left without water for too long while growing
+  Withered,
 }
 
 impl Stage {
@@ -367,17 +687,118 @@ impl Stage {
       //Self::Sprout => Sprout,
       Self::Mature => Self::Mature,
       Self::Display => Self::Display,
+      Self::Rotten => Self::Rotten,
+      Self::Withered => Self::Withered,
     }
   }
 
-  pub fn get_prefab(&self) -> &'static str {
+  pub fn is_growing(&self) -> bool {
+    matches!(self, Self::Seeds | Self::Seedling | Self::Flowering)
+  }
+}
+
+/// Tiles watered around the target tile, grown by holding the action down
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum WateringArea {
+  Single,
+  // Three tiles across the direction the player is facing
+  Line,
+  Square,
+}
+
+impl WateringArea {
+  /// Area reached after holding the action down for `charge` steps
+  pub fn from_charge(charge: u32) -> Option<Self> {
+    match charge {
+      0 => Some(Self::Single),
+      1 => Some(Self::Line),
+      2 => Some(Self::Square),
+      _ => None,
+    }
+  }
+
+  /// Offsets from the target tile in grid steps, facing along `facing`. The
+  /// target itself always comes first.
+  pub fn get_offsets(&self, facing: (i32, i32)) -> Vec<(i32, i32)> {
+    match self {
+      Self::Single => vec![(0, 0)],
+      Self::Line => {
+        let (x, z) = (facing.1, facing.0);
+        vec![(0, 0), (x, z), (-x, -z)]
+      }
+      Self::Square => {
+        let mut offsets = vec![(0, 0)];
+        for x in -1..=1 {
+          for z in -1..=1 {
+            if (x, z) != (0, 0) {
+              offsets.push((x, z));
+            }
+          }
+        }
+        offsets
+      }
+    }
+  }
+}
+
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  Default,
+  Eq,
+  PartialEq,
+  Serialize,
+  Deserialize,
+  Registerable,
+  Schema,
+  Duplicate,
+  tsify::Tsify,
+)]
+pub enum CanTier {
+  #[default]
+  Basic,
+  Copper,
+  Gold,
+}
+
+impl CanTier {
+  pub fn get_index(&self) -> usize {
+    *self as usize
+  }
+
+  pub fn get_next(&self) -> Option<Self> {
+    match self {
+      Self::Basic => Some(Self::Copper),
+      Self::Copper => Some(Self::Gold),
+      Self::Gold => None,
+    }
+  }
+
+  pub fn get_capacity(&self) -> f32 {
+    match self {
+      Self::Basic => 5.0,
+      Self::Copper => 10.0,
+      Self::Gold => 15.0,
+    }
+  }
+
+  /// Largest area the can waters at once when the action is held down
+  pub fn get_max_area(&self) -> WateringArea {
+    match self {
+      Self::Basic => WateringArea::Single,
+      Self::Copper => WateringArea::Line,
+      Self::Gold => WateringArea::Square,
+    }
+  }
+}
+
+impl std::fmt::Display for CanTier {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
-      Self::Seeds => "Seeds",
-      Self::Seedling => "Seedling",
-      Self::Flowering => "Flowering",
-      //Self::Sprout => "Sprout",
-      Self::Mature => "Mature",
-      Self::Display => "Display",
+      Self::Basic => write!(f, "Basic"),
+      Self::Copper => write!(f, "Copper"),
+      Self::Gold => write!(f, "Gold"),
     }
   }
 }
@@ -385,6 +806,18 @@ impl Stage {
 #[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
 pub struct WaterCan {
   pub level: Level,
+  #[serde(default)]
+  pub tier: CanTier,
+}
+
+impl WaterCan {
+  /// Moves the can up to the next tier, which holds more water
+  pub fn upgrade(&mut self) -> Option<CanTier> {
+    let tier = self.tier.get_next()?;
+    self.tier = tier;
+    self.level.max = tier.get_capacity();
+    Some(tier)
+  }
 }
 
 impl ProvideAssets for WaterCan {}
@@ -393,44 +826,479 @@ impl ProvideAssets for WaterCan {}
 pub struct Crop {
   pub crop: CropType,
   pub stage: Stage,
-  pub season_start: u32,
-  pub season_end: u32,
-
-  pub seed_timeout: Seconds,
-  pub seedling_timeout: Seconds,
-  pub flowering_timeout: Seconds,
-  pub mature_timeout: Seconds,
-
   pub phase_timing: Seconds,
-  pub award: usize,
+  // Growth stages the crop went through, and how many of them it spent on a
+  // watered tile
+  #[serde(default)]
+  pub stages_grown: u32,
+  #[serde(default)]
+  pub stages_watered: u32,
+  // Whether its tile was left dry for a whole day during the current stage
+  #[serde(default)]
+  pub dried_out: bool,
+  // Days in a row its tile was left dry, the crop withers after too many
+  #[serde(default)]
+  pub dry_days: u32,
+  // Tile the crop was planted on, for as long as it grows there
+  #[serde(default)]
+  pub tile: Option<PrefabId>,
+  // Quality tiers added by the fertilizer on its tile, rolled as the crop
+  // matures
+  #[serde(default)]
+  pub boost: u32,
 }
 
-impl ProvideAssets for Crop {}
+impl Crop {
+  pub fn get_quality(&self) -> Quality {
+    Quality::from_care(self.stages_watered, self.stages_grown).raise(self.boost)
+  }
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
-pub struct Harvestable {}
+impl ProvideAssets for Crop {}
 
-impl ProvideAssets for Harvestable {}
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  Default,
+  Eq,
+  PartialEq,
+  Hash,
+  Serialize,
+  Deserialize,
+  Registerable,
+  Schema,
+  Duplicate,
+  tsify::Tsify,
+)]
+pub enum Quality {
+  #[default]
+  Normal,
+  Silver,
+  Gold,
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
-pub struct DisplayItem {}
+impl Quality {
+  /// Gold when the crop was watered through every stage, silver when it was
+  /// watered for at least half of them
+  pub fn from_care(watered: u32, stages: u32) -> Self {
+    if stages == 0 {
+      Self::Normal
+    } else if watered >= stages {
+      Self::Gold
+    } else if watered * 2 >= stages {
+      Self::Silver
+    } else {
+      Self::Normal
+    }
+  }
 
-impl ProvideAssets for DisplayItem {}
+  pub fn raise(&self, tiers: u32) -> Self {
+    (0..tiers).fold(*self, |quality, _| match quality {
+      Self::Normal => Self::Silver,
+      Self::Silver | Self::Gold => Self::Gold,
+    })
+  }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
-pub struct SalesBin {}
+  pub fn get_price_multiplier(&self) -> f32 {
+    match self {
+      Self::Normal => 1.0,
+      Self::Silver => 1.25,
+      Self::Gold => 1.5,
+    }
+  }
 
-impl ProvideAssets for SalesBin {}
+  pub fn apply_to_price(&self, price: u64) -> u64 {
+    (price as f32 * self.get_price_multiplier()).round() as u64
+  }
+
+  pub fn get_color(&self) -> Option<Vector3<f32>> {
+    match self {
+      Self::Normal => None,
+      Self::Silver => Some(Vector3::new(0.75, 0.75, 0.8)),
+      Self::Gold => Some(Vector3::new(1.0, 0.84, 0.0)),
+    }
+  }
+}
+
+impl std::fmt::Display for Quality {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Normal => write!(f, "Normal"),
+      Self::Silver => write!(f, "Silver"),
+      Self::Gold => write!(f, "Gold"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+pub struct Harvestable {}
+
+impl ProvideAssets for Harvestable {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+pub struct DisplayItem {}
+
+impl ProvideAssets for DisplayItem {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+pub struct SalesBin {
+  #[serde(default)]
+  pub contents: Vec<Sale>,
+}
+
+impl SalesBin {
+  pub fn deposit(&mut self, seller: PrefabId, item: Item, quantity: usize) {
+    match self
+      .contents
+      .iter_mut()
+      .find(|sale| sale.seller == seller && sale.item == item)
+    {
+      Some(sale) => sale.quantity += quantity,
+      None => self.contents.push(Sale {
+        seller,
+        item,
+        quantity,
+      }),
+    }
+  }
+}
+
+impl ProvideAssets for SalesBin {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+pub struct Vendor {
+  pub stock: Vec<VendorStock>,
+}
+
+impl Vendor {
+  pub fn get_stock(&self, item: &Item) -> Option<&VendorStock> {
+    self.stock.iter().find(|stock| &stock.item == item)
+  }
+
+  pub fn get_stock_mut(&mut self, item: &Item) -> Option<&mut VendorStock> {
+    self.stock.iter_mut().find(|stock| &stock.item == item)
+  }
+
+  pub fn restock(&mut self) {
+    for stock in self.stock.iter_mut() {
+      stock.available = stock.restock;
+    }
+  }
+}
+
+impl ProvideAssets for Vendor {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+pub struct CraftingStation {
+  // Recipes from the `Catalog` that can be crafted here
+  pub recipes: Vec<RecipeId>,
+}
+
+impl ProvideAssets for CraftingStation {}
+
+/// Repairs every damaged tool, paying with `materials` when the character has
+/// them and with `cash` otherwise. Both costs are per tool. The watering can is
+/// upgraded on request from the station window, paying both costs of the
+/// upgrade.
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+pub struct RepairStation {
+  pub cash: u64,
+  #[serde(default)]
+  pub materials: Vec<RecipeItem>,
+  // Cost of upgrading the watering can out of the tier at the same index
+  #[serde(default)]
+  pub upgrades: Vec<CanUpgrade>,
+}
+
+#[derive(
+  Debug, Clone, PartialEq, Serialize, Deserialize, Registerable, Schema, Duplicate, tsify::Tsify,
+)]
+pub struct CanUpgrade {
+  pub cash: u64,
+  #[serde(default)]
+  pub materials: Vec<RecipeItem>,
+}
+
+impl RepairStation {
+  pub fn get_upgrade(&self, tier: CanTier) -> Option<&CanUpgrade> {
+    self.upgrades.get(tier.get_index())
+  }
+}
+
+impl ProvideAssets for RepairStation {}
+
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  Default,
+  Eq,
+  PartialEq,
+  Serialize,
+  Deserialize,
+  Registerable,
+  Schema,
+  Duplicate,
+)]
+pub enum SprinklerPattern {
+  // Tiles in line with the sprinkler
+  #[default]
+  Cross,
+  // Every tile in the square around the sprinkler
+  Square,
+}
+
+/// Waters the tiles around it at the start of every day, so they do not need
+/// watering by hand
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+pub struct Sprinkler {
+  pub range: u32,
+  #[serde(default)]
+  pub pattern: SprinklerPattern,
+}
+
+impl Sprinkler {
+  /// Whether the tile `x` and `z` tiles away from the sprinkler gets watered
+  pub fn covers(&self, x: i32, z: i32) -> bool {
+    let range = self.range as i32;
+    match self.pattern {
+      SprinklerPattern::Cross => (x == 0 || z == 0) && x.abs() + z.abs() <= range,
+      SprinklerPattern::Square => x.abs() <= range && z.abs() <= range,
+    }
+  }
+}
+
+impl ProvideAssets for Sprinkler {}
+
+/// Objects players put down on the farm grid, and get `item` back for when
+/// they pick them up again
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+pub struct Placeable {
+  pub item: Item,
+}
+
+impl ProvideAssets for Placeable {}
+
+#[derive(
+  Debug, Clone, PartialEq, Serialize, Deserialize, Registerable, Schema, Duplicate, tsify::Tsify,
+)]
+pub struct VendorStock {
+  pub item: Item,
+  pub price: u64,
+  // How many are available again at the start of each day
+  pub restock: usize,
+  #[serde(default)]
+  pub available: usize,
+}
+
+/// Storage shared by every player. Its contents replicate to everyone, but only
+/// the server ever changes them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+pub struct Chest {
+  pub inventory: Vec<InventoryItem>,
+}
+
+impl Chest {
+  pub fn is_empty(&self) -> bool {
+    self.inventory.iter().all(|slot| slot.item == Item::Nothing)
+  }
+
+  pub fn store(&mut self, item: Item, quantity: usize) -> Result<(), InventoryError> {
+    match stack_into(&mut self.inventory, &item, quantity) {
+      0 => Ok(()),
+      remaining => Err(InventoryError::Full { item, remaining }),
+    }
+  }
+
+  pub fn take_slot(
+    &mut self,
+    slot: usize,
+    quantity: usize,
+  ) -> Result<InventoryItem, InventoryError> {
+    match self.inventory.get_mut(slot) {
+      Some(inventory) => inventory.take(quantity),
+      None => Err(InventoryError::InvalidSlot { slot }),
+    }
+  }
+}
+
+impl ProvideAssets for Chest {}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+pub struct Sale {
+  pub seller: PrefabId,
+  pub item: Item,
+  pub quantity: usize,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
 pub struct Rock {
   pub health: Level,
+  // Items left on the ground once the rock breaks
+  #[serde(default)]
+  pub drops: Vec<RecipeItem>,
+  // In-game days until a broken rock grows back where it was
+  #[serde(default = "Rock::default_respawn_days")]
+  pub respawn_days: u32,
+}
+
+impl Rock {
+  fn default_respawn_days() -> u32 {
+    3
+  }
+
+  /// Chips one hit off the rock, returning whether it broke
+  pub fn hit(&mut self) -> bool {
+    self.health.remove(1.0);
+    self.health.current <= self.health.min
+  }
 }
 
 impl ProvideAssets for Rock {}
 
 #[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
-pub struct Durability {}
+pub struct Tree {
+  pub health: Level,
+  // Logs awarded to whoever fells the tree
+  #[serde(default = "Tree::default_logs")]
+  pub logs: usize,
+  // In-game days a stump takes to grow back into a tree
+  #[serde(default = "Tree::default_regrow_days")]
+  pub regrow_days: u32,
+  // Day the tree grows back, only set while it is a stump
+  #[serde(default)]
+  pub regrows_on: Option<u32>,
+}
+
+impl Tree {
+  fn default_logs() -> usize {
+    3
+  }
+
+  fn default_regrow_days() -> u32 {
+    5
+  }
+
+  pub fn is_stump(&self) -> bool {
+    self.regrows_on.is_some()
+  }
+
+  /// Chips one hit off the tree, returning whether it was felled
+  pub fn hit(&mut self) -> bool {
+    self.health.remove(1.0);
+    self.health.current <= self.health.min
+  }
+
+  pub fn fell(&mut self, day: u32) {
+    self.regrows_on = Some(day + self.regrow_days);
+  }
+
+  pub fn regrow(&mut self) {
+    self.health.current = self.health.max;
+    self.regrows_on = None;
+  }
+}
+
+impl ProvideAssets for Tree {}
+
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  Eq,
+  PartialEq,
+  Hash,
+  Serialize,
+  Deserialize,
+  Registerable,
+  Schema,
+  Duplicate,
+  tsify::Tsify,
+)]
+pub enum ToolType {
+  WateringCan,
+  Hoe,
+  Axe,
+  Pickaxe,
+}
+
+impl std::fmt::Display for ToolType {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::WateringCan => write!(f, "Watering Can"),
+      Self::Hoe => write!(f, "Hoe"),
+      Self::Axe => write!(f, "Axe"),
+      Self::Pickaxe => write!(f, "Pickaxe"),
+    }
+  }
+}
+
+/// Wear of every tool a character owns. Tools that are not listed never break.
+#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+pub struct Durability {
+  #[serde(default)]
+  pub tools: HashMap<ToolType, Level>,
+
+  // Last tool that refused its action because it is broken, so the client can
+  // let the player know
+  #[serde(skip)]
+  pub refused: Option<ToolType>,
+}
+
+impl Durability {
+  pub fn is_broken(&self, tool: &ToolType) -> bool {
+    match self.tools.get(tool) {
+      Some(level) => level.current <= level.min,
+      None => false,
+    }
+  }
+
+  /// Wears `tool` down by one use, or refuses when it is already broken
+  pub fn use_tool(&mut self, tool: ToolType) -> Result<(), ToolError> {
+    match self.tools.get_mut(&tool) {
+      Some(level) if level.current <= level.min => {
+        self.refused = Some(tool);
+        Err(ToolError::Broken { tool })
+      }
+      Some(level) => {
+        level.remove(1.0);
+        Ok(())
+      }
+      None => Ok(()),
+    }
+  }
+
+  pub fn get_damaged(&self) -> Vec<ToolType> {
+    self
+      .tools
+      .iter()
+      .filter(|(_, level)| level.current < level.max)
+      .map(|(tool, _)| *tool)
+      .collect()
+  }
+
+  pub fn repair(&mut self, tool: &ToolType) {
+    if let Some(level) = self.tools.get_mut(tool) {
+      level.current = level.max;
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolError {
+  Broken { tool: ToolType },
+}
+
+impl std::fmt::Display for ToolError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Broken { tool } => write!(f, "The {:} is broken", tool),
+    }
+  }
+}
+
+impl std::error::Error for ToolError {}
 
 impl ProvideAssets for Durability {}
 
@@ -446,9 +1314,21 @@ pub struct TimeOfDay {
   pub current_time: f32,
   pub total_time: f32,
   pub delta_time: f32,
+  #[serde(default)]
+  pub day: u32,
+  #[serde(default = "TimeOfDay::default_days_per_season")]
+  pub days_per_season: u32,
 }
 
 impl TimeOfDay {
+  fn default_days_per_season() -> u32 {
+    28
+  }
+
+  pub fn get_calendar(&self) -> Calendar {
+    Calendar::from_day(self.day, self.days_per_season)
+  }
+
   pub fn get_percent(&self) -> f32 {
     self.current_time / self.total_time
   }
@@ -464,18 +1344,40 @@ impl TimeOfDay {
 
 impl ProvideAssets for TimeOfDay {}
 
-#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
-pub enum SeedTypes {
-  Pumpkin,
-}
-
+/// Name of the farm action a character has selected, one of those in the
+/// `farming` registry, e.g. `"WaterTile"`
 #[derive(
-  Debug, Clone, PartialEq, Serialize, Deserialize, Registerable, Schema, Duplicate, tsify::Tsify,
+  Debug,
+  Clone,
+  Eq,
+  PartialEq,
+  Hash,
+  Serialize,
+  Deserialize,
+  Registerable,
+  Schema,
+  Duplicate,
+  tsify::Tsify,
 )]
-pub enum ActionTypes {
-  WaterTile,
-  ThrowSeed,
-  Harvest,
+#[serde(transparent)]
+pub struct ActionTypes(pub String);
+
+impl std::fmt::Display for ActionTypes {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{:}", self.0)
+  }
+}
+
+impl From<&str> for ActionTypes {
+  fn from(name: &str) -> Self {
+    Self(String::from(name))
+  }
+}
+
+impl ActionTypes {
+  pub fn is(&self, name: &str) -> bool {
+    self.0 == name
+  }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
@@ -503,8 +1405,124 @@ impl ProvideAssets for HouseEntrance {}
 pub struct Friend {}
 impl ProvideAssets for Friend {}
 
+/// Farm soil starts out untilled, is tilled with a hoe, watered and finally
+/// planted. Planted soil still dries out every morning and needs watering again
+/// for its crop to grow, and goes back to untilled once the crop is cleared.
+#[derive(
+  Debug,
+  Clone,
+  Copy,
+  Default,
+  Eq,
+  PartialEq,
+  Serialize,
+  Deserialize,
+  Registerable,
+  Schema,
+  Duplicate,
+  tsify::Tsify,
+)]
+pub enum Soil {
+  #[default]
+  Untilled,
+  Tilled,
+  Watered,
+  Planted {
+    watered: bool,
+  },
+}
+
+impl Soil {
+  pub fn tilled(&self) -> Option<Self> {
+    match self {
+      Self::Untilled => Some(Self::Tilled),
+      _ => None,
+    }
+  }
+
+  pub fn watered(&self) -> Option<Self> {
+    match self {
+      Self::Tilled => Some(Self::Watered),
+      Self::Planted { watered: false } => Some(Self::Planted { watered: true }),
+      _ => None,
+    }
+  }
+
+  pub fn planted(&self) -> Option<Self> {
+    match self {
+      Self::Watered => Some(Self::Planted { watered: true }),
+      _ => None,
+    }
+  }
+
+  pub fn dried(&self) -> Option<Self> {
+    match self {
+      Self::Watered => Some(Self::Tilled),
+      Self::Planted { watered: true } => Some(Self::Planted { watered: false }),
+      _ => None,
+    }
+  }
+
+  pub fn cleared(&self) -> Option<Self> {
+    match self {
+      Self::Planted { .. } => Some(Self::Untilled),
+      _ => None,
+    }
+  }
+
+  pub fn is_watered(&self) -> bool {
+    matches!(self, Self::Watered | Self::Planted { watered: true })
+  }
+
+  pub fn is_tilled(&self) -> bool {
+    !matches!(self, Self::Untilled)
+  }
+
+  /// Tile prefab whose model shows the soil in this state
+  pub fn get_prefab(&self) -> &'static str {
+    match self {
+      Self::Untilled => "Prefab::Tile Grass",
+      Self::Tilled | Self::Planted { watered: false } => "Prefab::Tile Dirt",
+      Self::Watered | Self::Planted { watered: true } => "Prefab::Wet Dirt",
+    }
+  }
+}
+
+/// Fertilizer spread on a tile, which wears off once the season is over
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Registerable, Schema, Duplicate)]
+pub struct Fertilized {
+  pub fertilizer: FertilizerType,
+  // Season it was spread in, see `Calendar::get_season_count`
+  pub season: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
-pub struct Tile {}
+pub struct Tile {
+  #[serde(default)]
+  pub soil: Soil,
+  #[serde(default)]
+  pub fertilized: Option<Fertilized>,
+}
+
+impl Tile {
+  pub fn get_fertilizer(&self) -> Option<&'static FertilizerEntry> {
+    match &self.fertilized {
+      Some(fertilized) => fertilized.fertilizer.get_entry(),
+      None => None,
+    }
+  }
+
+  pub fn can_fertilize(&self) -> bool {
+    self.soil.is_tilled() && self.fertilized.is_none()
+  }
+
+  /// Fertilized tiles are tinted in the color of their fertilizer
+  pub fn get_tint(&self) -> Option<Vector3<f32>> {
+    self
+      .get_fertilizer()
+      .map(|entry| Vector3::from(entry.color))
+  }
+}
 
 impl ProvideAssets for Tile {}
 
@@ -521,16 +1539,6 @@ pub struct WaterSource {
 
 impl ProvideAssets for WaterSource {}
 
-#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
-pub struct WateredTile {}
-
-impl ProvideAssets for WateredTile {}
-
-#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
-pub struct CropTile {}
-
-impl ProvideAssets for CropTile {}
-
 #[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
 pub struct NextPhase {
   prefab_name: String,
@@ -539,13 +1547,6 @@ pub struct NextPhase {
 
 impl ProvideAssets for NextPhase {}
 
-#[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
-pub struct Seeds {
-  pub pumpkins: usize,
-}
-
-impl ProvideAssets for Seeds {}
-
 // NOTE: Anti-pattern. We should revisit this.
 #[derive(Debug, Clone, Serialize, Deserialize, Registerable, Schema, Duplicate)]
 pub struct Preloader {
@@ -568,7 +1569,6 @@ impl ProvideAssets for ActiveCamera {}
 #[derive(
   Debug,
   Clone,
-  Copy,
   Eq,
   PartialEq,
   Hash,
@@ -581,8 +1581,11 @@ impl ProvideAssets for ActiveCamera {}
 )]
 pub enum Item {
   Nothing,
-  Crop(CropType),
+  Crop(CropType, Quality),
   Seed(CropType),
+  Material(MaterialType),
+  Tool(ToolType),
+  Fertilizer(FertilizerType),
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Registerable, Schema, Duplicate)]
@@ -594,16 +1597,142 @@ pub struct InventoryItem {
   pub quantity: Quantity,
 }
 
-impl std::fmt::Display for Item {
+impl InventoryItem {
+  pub fn clear(&mut self) {
+    self.item = Item::Nothing;
+    self.quantity = Quantity::Empty;
+  }
+
+  /// Takes `quantity` out of the slot, emptying it when nothing is left
+  pub fn take(&mut self, quantity: usize) -> Result<InventoryItem, InventoryError> {
+    if self.item == Item::Nothing || quantity == 0 {
+      return Err(InventoryError::NotEnough {
+        item: self.item.clone(),
+        requested: quantity,
+        available: 0,
+      });
+    }
+
+    let item = self.item.clone();
+    self.quantity.decrement_by(&item, quantity)?;
+
+    if let Quantity::Empty = self.quantity {
+      self.clear();
+    }
+
+    Ok(InventoryItem {
+      item,
+      quantity: Quantity::Finite(quantity),
+    })
+  }
+}
+
+/// Tops up the stacks of `search` before taking empty slots, returning how many
+/// did not fit.
+pub fn stack_into(slots: &mut [InventoryItem], search: &Item, increase: usize) -> usize {
+  let stack_size = search.get_stack_size();
+  let mut remaining = increase;
+
+  for inventory in slots
+    .iter_mut()
+    .filter(|inventory| &inventory.item == search)
+  {
+    remaining -= inventory.quantity.fill(remaining, stack_size);
+  }
+
+  for inventory in slots
+    .iter_mut()
+    .filter(|inventory| inventory.item == Item::Nothing)
+  {
+    if remaining == 0 {
+      break;
+    }
+
+    inventory.item = search.clone();
+    inventory.quantity = Quantity::Empty;
+    remaining -= inventory.quantity.fill(remaining, stack_size);
+  }
+
+  remaining
+}
+
+/// Slot operations requested by the inventory UI. They travel to the server
+/// with the player inputs, and are only ever applied there.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, tsify::Tsify)]
+pub enum InventoryCommand {
+  Select { slot: usize },
+  Swap { from: usize, to: usize },
+  Move { from: usize, to: usize },
+  Split { slot: usize, quantity: usize },
+  Discard { slot: usize },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InventoryError {
+  InvalidSlot {
+    slot: usize,
+  },
+  Full {
+    item: Item,
+    remaining: usize,
+  },
+  NotEnough {
+    item: Item,
+    requested: usize,
+    available: usize,
+  },
+}
+
+impl std::fmt::Display for InventoryError {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     match self {
-      Self::Nothing => write!(f, "Nothing"),
-      Self::Crop(crop) => write!(f, "{:} - Crop", crop),
-      Self::Seed(crop) => write!(f, "{:} - Seed", crop),
+      Self::InvalidSlot { slot } => write!(f, "There is no inventory slot {:}", slot),
+      Self::Full { item, remaining } => {
+        write!(
+          f,
+          "Inventory is full, {:} x {:} did not fit",
+          remaining, item
+        )
+      }
+      Self::NotEnough {
+        item,
+        requested,
+        available,
+      } => write!(
+        f,
+        "Not enough {:}, wanted {:} but only {:} available",
+        item, requested, available
+      ),
     }
   }
 }
 
+impl std::error::Error for InventoryError {}
+
+impl Item {
+  pub fn get_display_name(&self) -> String {
+    Catalog::get().get_display_name(self)
+  }
+
+  pub fn get_stack_size(&self) -> usize {
+    Catalog::get().get_stack_size(self)
+  }
+
+  pub fn get_price(&self) -> Option<u64> {
+    Catalog::get().get_price(self)
+  }
+
+  pub fn get_placed_prefab(&self) -> Option<&'static str> {
+    Catalog::get().get_placed_prefab(self)
+  }
+}
+
+impl std::fmt::Display for Item {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{:}", self.get_display_name())
+  }
+}
+
 impl ProvideAssets for Inventory {}
 
 #[derive(
@@ -641,17 +1770,54 @@ impl Quantity {
     }
   }
 
-  pub fn decrement_by(&mut self, decrement: usize) {
+  /// Takes `decrement` away, failing without changing anything when there is
+  /// not enough. `item` is what the quantity counts, reported in the error.
+  pub fn decrement_by(&mut self, item: &Item, decrement: usize) -> Result<(), InventoryError> {
     match self {
-      Quantity::Finite(value) => {
-        if *value > decrement {
-          *value -= decrement;
-        } else if *value == decrement {
-          *self = Quantity::Empty;
-        }
+      Quantity::Finite(value) if *value > decrement => {
+        *value -= decrement;
+      }
+      Quantity::Finite(value) if *value == decrement => {
+        *self = Quantity::Empty;
       }
       Quantity::Infinite => {}
-      Quantity::Empty => {}
+      Quantity::Empty if decrement == 0 => {}
+      _ => {
+        return Err(InventoryError::NotEnough {
+          item: item.clone(),
+          requested: decrement,
+          available: self.get_available(),
+        })
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Adds up to `amount` without going over `limit`, returning how many were added
+  pub fn fill(&mut self, amount: usize, limit: usize) -> usize {
+    match self {
+      Quantity::Infinite => amount,
+      Quantity::Empty => {
+        let added = amount.min(limit);
+        if added > 0 {
+          *self = Quantity::Finite(added);
+        }
+        added
+      }
+      Quantity::Finite(value) => {
+        let added = amount.min(limit.saturating_sub(*value));
+        *value += added;
+        added
+      }
+    }
+  }
+
+  pub fn get_available(&self) -> usize {
+    match self {
+      Quantity::Infinite => usize::MAX,
+      Quantity::Empty => 0,
+      Quantity::Finite(value) => *value,
     }
   }
 
@@ -0,0 +1,275 @@
+use crate::shared::catalog::Catalog;
+use crate::shared::components::{
+  Character, CharacterState, CraftingStation, FarmWork, InventoryError, Item,
+};
+use crate::shared::farming::{is_in_reach, FarmAction};
+use crate::shared::game_input::GameInput;
+use engine::{
+  application::scene::{Scene, TransformComponent},
+  nalgebra::Vector3,
+  systems::{Backpack, Initializable, Inventory, System},
+  tsify,
+  utils::units::Seconds,
+  Entity,
+};
+use serde::{Deserialize, Serialize};
+use tagged::{Duplicate, Registerable, Schema};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::shared::pickups::spawn_drop;
+
+#[cfg(target_arch = "wasm32")]
+use crate::client::browser::Message;
+#[cfg(target_arch = "wasm32")]
+use crate::shared::farming::{get_local_player, get_used_target};
+#[cfg(target_arch = "wasm32")]
+use engine::systems::browser::BrowserController;
+
+/// Key into the recipes of the `Catalog`, e.g. `"Fence"`
+#[derive(
+  Debug,
+  Clone,
+  Eq,
+  PartialEq,
+  Hash,
+  Serialize,
+  Deserialize,
+  Registerable,
+  Schema,
+  Duplicate,
+  tsify::Tsify,
+)]
+#[serde(transparent)]
+pub struct RecipeId(pub String);
+
+impl RecipeId {
+  pub fn get_recipe(&self) -> Option<&'static Recipe> {
+    Catalog::get().get_recipe(self)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, tsify::Tsify)]
+pub struct RecipeItem {
+  pub item: Item,
+  pub quantity: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, tsify::Tsify)]
+pub struct Recipe {
+  pub display_name: String,
+  pub inputs: Vec<RecipeItem>,
+  pub output: RecipeItem,
+  pub craft_time: Seconds,
+}
+
+impl Recipe {
+  /// Takes every input out of the inventory, or nothing at all when one of
+  /// them is missing.
+  pub fn consume(&self, character: &mut Character) -> Result<(), InventoryError> {
+    character.remove_all(&self.inputs)
+  }
+}
+
+/// Uses a crafting station, which opens its window to pick a recipe from. The
+/// recipe picked is crafted as the work of this action, handing its output to
+/// the player once done.
+#[derive(Default)]
+pub struct Craft {}
+
+impl FarmAction for Craft {
+  fn name(&self) -> &'static str {
+    "Craft"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Craft"
+  }
+
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Crafting...")
+  }
+
+  fn is_selectable(&self) -> bool {
+    false
+  }
+
+  fn get_color(&self) -> Vector3<f32> {
+    Vector3::new(1.0, 0.0, 1.0)
+  }
+
+  fn get_candidates(&self, scene: &mut Scene, _: Entity) -> Vec<Entity> {
+    let mut stations = vec![];
+    for (entity, _) in scene.query_mut::<&CraftingStation>() {
+      stations.push(entity);
+    }
+    stations
+  }
+
+  // Crafting starts from the crafting window, with the recipe the player
+  // picked, so pressing the action button only opens it
+  fn start(&self, _: &mut Scene, _: &mut Backpack, _: Entity, _: Entity) -> Option<FarmWork> {
+    None
+  }
+
+  fn complete(&self, scene: &mut Scene, _: &mut Backpack, player: Entity, work: FarmWork) {
+    let recipe = match work
+      .recipe
+      .as_ref()
+      .and_then(|recipe_id| recipe_id.get_recipe())
+    {
+      Some(recipe) => recipe,
+      None => return,
+    };
+
+    let mut overflow = None;
+    if let Some((transform, character)) =
+      scene.get_components_mut::<(&TransformComponent, &mut Character)>(player)
+    {
+      match character.award(recipe.output.item.clone(), recipe.output.quantity) {
+        Ok(_) => {}
+        Err(InventoryError::Full { item, remaining }) => {
+          overflow = Some((item, remaining, transform.clone()));
+        }
+        Err(error) => log::warn!("crafting {:} failed: {:}", recipe.display_name, error),
+      }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some((item, remaining, transform)) = overflow {
+      spawn_drop(scene, item, remaining, &transform);
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = overflow;
+  }
+}
+
+pub struct CraftingSystem {
+  #[cfg(target_arch = "wasm32")]
+  browser: BrowserController<Message>,
+  #[cfg(target_arch = "wasm32")]
+  open: Option<Entity>,
+}
+
+impl Initializable for CraftingSystem {
+  #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+  fn initialize(inventory: &Inventory) -> Self {
+    #[cfg(target_arch = "wasm32")]
+    let browser = inventory.get::<BrowserController<Message>>().clone();
+    Self {
+      #[cfg(target_arch = "wasm32")]
+      browser,
+      #[cfg(target_arch = "wasm32")]
+      open: None,
+    }
+  }
+}
+
+impl CraftingSystem {
+  /// Crafts what players pick from the crafting window, at a station in their
+  /// reach that has the recipe.
+  pub fn handle_requests(&self, scene: &mut Scene) {
+    let mut requests = vec![];
+    for (entity, input) in scene.query_mut::<&GameInput>() {
+      if !input.crafts.is_empty() {
+        requests.push((entity, input.crafts.clone()));
+      }
+    }
+
+    let mut stations = vec![];
+    for (entity, station) in scene.query_mut::<&CraftingStation>() {
+      stations.push((entity, station.recipes.clone()));
+    }
+
+    for (player_entity, crafts) in requests {
+      let nearby = stations
+        .iter()
+        .filter(|(entity, _)| is_in_reach(scene, player_entity, *entity))
+        .collect::<Vec<_>>();
+
+      let (character, state) =
+        match scene.get_components_mut::<(&mut Character, &mut CharacterState)>(player_entity) {
+          Some(components) => components,
+          None => continue,
+        };
+
+      for recipe_id in &crafts {
+        if !matches!(state, CharacterState::Normal | CharacterState::Running) {
+          break;
+        }
+
+        let recipe = match recipe_id.get_recipe() {
+          Some(recipe) => recipe,
+          None => continue,
+        };
+
+        let station_entity = match nearby
+          .iter()
+          .find(|(_, recipes)| recipes.contains(recipe_id))
+        {
+          Some((entity, _)) => *entity,
+          None => {
+            log::warn!("can't craft {:}: not at a station", recipe.display_name);
+            continue;
+          }
+        };
+
+        match recipe.consume(character) {
+          Ok(()) => {
+            let work = FarmWork::new("Craft", station_entity, recipe.craft_time)
+              .with_recipe(recipe_id.clone());
+            *state = CharacterState::Farming(work);
+          }
+          Err(error) => log::warn!("can't craft {:}: {:}", recipe.display_name, error),
+        }
+      }
+    }
+  }
+
+  /// Opens the crafting window with the recipes of the station the player
+  /// uses, and closes it once they walk away.
+  #[cfg(target_arch = "wasm32")]
+  pub fn handle_crafting_window(&mut self, scene: &mut Scene) {
+    let mut nearby = get_used_target(scene, "Craft");
+    let player = get_local_player(scene);
+    if nearby.is_none()
+      && let Some(open_entity) = self.open
+      && let Some(player_entity) = player
+      && is_in_reach(scene, player_entity, open_entity)
+    {
+      nearby = Some(open_entity);
+    }
+
+    match nearby {
+      Some(entity) if self.open != Some(entity) => {
+        if let Some(station) = scene.get_components_mut::<&CraftingStation>(entity) {
+          let recipes = station
+            .recipes
+            .iter()
+            .filter_map(|id| id.get_recipe().map(|recipe| (id.clone(), recipe.clone())))
+            .collect();
+
+          self.browser.send(Message::OpenCrafting { recipes });
+          self.open = Some(entity);
+        }
+      }
+      Some(_) => {}
+      None => {
+        if self.open.take().is_some() {
+          self.browser.send(Message::CloseCrafting);
+        }
+      }
+    }
+  }
+}
+
+impl System for CraftingSystem {
+  fn get_name(&self) -> &'static str {
+    "CraftingSystem"
+  }
+
+  fn run(&mut self, scene: &mut Scene, _: &mut Backpack) {
+    self.handle_requests(scene);
+    #[cfg(target_arch = "wasm32")]
+    self.handle_crafting_window(scene);
+  }
+}
@@ -0,0 +1,629 @@
+use crate::shared::chopping::Chop;
+use crate::shared::components::{
+  Character, CharacterState, Crop, Durability, FarmWork, Fertilized, Harvestable, InventoryError,
+  Item, Level, MaterialType, Movement, Placeable, Soil, Stage, Tile, ToolType, WaterCan,
+  WaterSource, WateringArea,
+};
+use crate::shared::crafting::Craft;
+use crate::shared::farming::FarmAction;
+use crate::shared::game_input::{GameInput, InputState};
+use crate::shared::mining::Mine;
+use crate::shared::pickups::{
+  change_soil, change_stage, find_tile, is_in_season, Collect, Sell, Showoff,
+};
+use crate::shared::placement::{is_same_tile, PickUp, PlaceItem};
+use crate::shared::shop::Shop;
+use crate::shared::storage::OpenChest;
+use crate::shared::timeofday::DayCycle;
+use crate::shared::tools::{use_tool, Repair};
+use engine::{
+  application::{
+    components::{ModelComponent, ParentComponent},
+    scene::{IdComponent, Scene, TransformComponent},
+  },
+  nalgebra::Vector3,
+  systems::{Backpack, Registry},
+  utils::units::Seconds,
+  Entity,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::shared::pickups::spawn_drop;
+
+pub struct FarmActions {}
+
+impl Registry for FarmActions {
+  fn register() {
+    use crate::shared::farming::Access;
+    CollectWater::register();
+    Till::register();
+    WaterTile::register();
+    Fertilize::register();
+    ThrowSeed::register();
+    Harvest::register();
+    PlaceItem::register();
+    PickUp::register();
+    Mine::register();
+    Chop::register();
+    Craft::register();
+    Shop::register();
+    OpenChest::register();
+    Repair::register();
+    Sell::register();
+    Collect::register();
+  }
+}
+
+/// Snaps the direction a player is facing to the closest of the four grid
+/// directions, as steps along x and z
+fn get_grid_facing(direction: &Vector3<f32>) -> (i32, i32) {
+  match direction.x.abs() > direction.z.abs() {
+    true => (direction.x.signum() as i32, 0),
+    false => (0, direction.z.signum() as i32),
+  }
+}
+
+/// Every tile that passes `filter`, leaving out those with an object placed on
+/// them so nothing is tilled, watered or planted underneath it. Returns each
+/// tile along with its position.
+fn get_open_tiles(
+  scene: &mut Scene,
+  filter: impl Fn(&Tile) -> bool,
+) -> Vec<(Entity, Vector3<f32>)> {
+  let mut placed = vec![];
+  for (_, (transform, _)) in scene.query_mut::<(&TransformComponent, &Placeable)>() {
+    placed.push(transform.translation);
+  }
+
+  let mut tiles = vec![];
+  for (entity, (transform, tile)) in scene.query_mut::<(&TransformComponent, &Tile)>() {
+    let is_occupied = placed
+      .iter()
+      .any(|position| is_same_tile(position, &transform.translation));
+    if filter(tile) && !is_occupied {
+      tiles.push((entity, transform.translation));
+    }
+  }
+  tiles
+}
+
+/// Every tile that passes `filter` and has nothing placed on it
+pub fn get_tiles(scene: &mut Scene, filter: impl Fn(&Tile) -> bool) -> Vec<Entity> {
+  get_open_tiles(scene, filter)
+    .into_iter()
+    .map(|(entity, _)| entity)
+    .collect()
+}
+
+#[derive(Default)]
+pub struct CollectWater {}
+
+impl FarmAction for CollectWater {
+  fn name(&self) -> &'static str {
+    "CollectWater"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Collect Water"
+  }
+
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Collecting Water..")
+  }
+
+  fn is_selectable(&self) -> bool {
+    false
+  }
+
+  // Water sources are often right next to the field, and should not take the
+  // target away from the tile the player is facing
+  fn is_fallback(&self) -> bool {
+    true
+  }
+
+  fn get_color(&self) -> Vector3<f32> {
+    Vector3::new(0.0, 0.0, 1.0)
+  }
+
+  fn get_candidates(&self, scene: &mut Scene, _: Entity) -> Vec<Entity> {
+    let mut sources = vec![];
+    for (entity, _) in scene.query_mut::<&WaterSource>() {
+      sources.push(entity);
+    }
+    sources
+  }
+
+  fn start(
+    &self,
+    scene: &mut Scene,
+    _: &mut Backpack,
+    player: Entity,
+    target: Entity,
+  ) -> Option<FarmWork> {
+    let can = scene.get_components_mut::<&mut WaterCan>(player)?;
+    can.level.maximize_with_rate(0.125);
+    Some(FarmWork::new(self.name(), target, Seconds::new(1.0)))
+  }
+
+  // The can fills up at its own rate, so the work lasts as long as that takes
+  fn progress(&self, scene: &mut Scene, player: Entity, _: &mut FarmWork) -> bool {
+    match scene.get_components_mut::<&mut WaterCan>(player) {
+      Some(can) => can.level.tick().is_some(),
+      None => true,
+    }
+  }
+
+  fn complete(&self, _: &mut Scene, _: &mut Backpack, _: Entity, _: FarmWork) {}
+}
+
+#[derive(Default)]
+pub struct Till {}
+
+impl FarmAction for Till {
+  fn name(&self) -> &'static str {
+    "Till"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Till Soil"
+  }
+
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Tilling Soil..")
+  }
+
+  fn get_candidates(&self, scene: &mut Scene, _: Entity) -> Vec<Entity> {
+    get_tiles(scene, |tile| tile.soil.tilled().is_some())
+  }
+
+  fn start(
+    &self,
+    scene: &mut Scene,
+    _: &mut Backpack,
+    player: Entity,
+    target: Entity,
+  ) -> Option<FarmWork> {
+    let (character, durability) =
+      scene.get_components_mut::<(&Character, Option<&mut Durability>)>(player)?;
+    if !character.is_holding(&Item::Tool(ToolType::Hoe)) {
+      return None;
+    }
+    use_tool(durability, ToolType::Hoe).ok()?;
+
+    Some(FarmWork::new(self.name(), target, Seconds::new(2.0)))
+  }
+
+  fn complete(&self, scene: &mut Scene, _: &mut Backpack, _: Entity, work: FarmWork) {
+    if let Some(tile_entity) = work.get_target() {
+      change_soil(scene, tile_entity, Soil::tilled);
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct WaterTile {}
+
+impl WaterTile {
+  /// Dry tiles covered by `area` around the `target` tile, leaving out those
+  /// with an object placed on them
+  fn get_area_tiles(
+    scene: &mut Scene,
+    target: Entity,
+    area: WateringArea,
+    facing: (i32, i32),
+  ) -> Vec<Entity> {
+    let center = match scene.get_components_mut::<&TransformComponent>(target) {
+      Some(transform) => transform.translation,
+      None => return vec![],
+    };
+    let tiles = get_open_tiles(scene, |tile| tile.soil.watered().is_some());
+
+    let mut targets = vec![];
+    for (x, z) in area.get_offsets(facing) {
+      let position = center + Vector3::new(x as f32, 0.0, z as f32);
+      if let Some((entity, _)) = tiles
+        .iter()
+        .find(|(_, translation)| is_same_tile(translation, &position))
+      {
+        targets.push(*entity);
+      }
+    }
+    targets
+  }
+}
+
+impl FarmAction for WaterTile {
+  fn name(&self) -> &'static str {
+    "WaterTile"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Water Soil"
+  }
+
+  fn get_progress_label(&self, work: &FarmWork) -> String {
+    match work.charge {
+      Some(_) => String::from("Charging Water.."),
+      None => String::from("Watering Soil.."),
+    }
+  }
+
+  fn get_candidates(&self, scene: &mut Scene, _: Entity) -> Vec<Entity> {
+    get_tiles(scene, |tile| tile.soil.watered().is_some())
+  }
+
+  fn start(
+    &self,
+    scene: &mut Scene,
+    _: &mut Backpack,
+    player: Entity,
+    target: Entity,
+  ) -> Option<FarmWork> {
+    let (character, can, durability) =
+      scene.get_components_mut::<(&Character, &WaterCan, Option<&mut Durability>)>(player)?;
+    if !character.is_holding(&Item::Tool(ToolType::WateringCan)) || can.level.current < 1.0 {
+      return None;
+    }
+    use_tool(durability, ToolType::WateringCan).ok()?;
+
+    Some(FarmWork {
+      charge: Some(0),
+      ..FarmWork::new(self.name(), target, Seconds::new(1.0))
+    })
+  }
+
+  // Holding the action down grows the area as far as the can allows, and
+  // letting go waters it
+  fn progress(&self, scene: &mut Scene, player: Entity, work: &mut FarmWork) -> bool {
+    let charge = match work.charge {
+      Some(charge) => charge,
+      None => {
+        return match scene.get_components_mut::<&mut WaterCan>(player) {
+          Some(can) => can.level.tick().is_some(),
+          None => true,
+        }
+      }
+    };
+
+    let (is_held, facing, max_area) =
+      match scene.get_components_mut::<(&GameInput, &Movement, &WaterCan)>(player) {
+        Some((input, movement, can)) => (
+          input.check(InputState::Action),
+          get_grid_facing(&movement.direction),
+          can.tier.get_max_area(),
+        ),
+        None => return true,
+      };
+
+    if is_held {
+      if let Some(_) = work.timing.tick()
+        && let Some(next) = WateringArea::from_charge(charge + 1)
+        && next <= max_area
+      {
+        work.charge = Some(charge + 1);
+        work.timing = Level::to_max(1.0, Seconds::new(1.0));
+      }
+      return false;
+    }
+
+    let area = WateringArea::from_charge(charge).unwrap_or(WateringArea::Single);
+    let mut targets = match work.get_target() {
+      Some(target) => Self::get_area_tiles(scene, target, area, facing),
+      None => vec![],
+    };
+
+    let can = match scene.get_components_mut::<&mut WaterCan>(player) {
+      Some(can) => can,
+      None => return true,
+    };
+
+    // Every tile takes the same amount of water, so the can only stretches as
+    // far as what is left in it
+    targets.truncate(can.level.current.floor() as usize);
+    if targets.is_empty() {
+      work.targets = targets;
+      return true;
+    }
+
+    can
+      .level
+      .change_by(-(targets.len() as f32), Seconds::new(4.0));
+    work.targets = targets;
+    work.charge = None;
+    false
+  }
+
+  fn complete(&self, scene: &mut Scene, _: &mut Backpack, _: Entity, work: FarmWork) {
+    for tile_entity in work.targets {
+      change_soil(scene, tile_entity, Soil::watered);
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct Fertilize {}
+
+impl FarmAction for Fertilize {
+  fn name(&self) -> &'static str {
+    "Fertilize"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Fertilize Soil"
+  }
+
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Fertilizing Soil..")
+  }
+
+  fn get_candidates(&self, scene: &mut Scene, _: Entity) -> Vec<Entity> {
+    get_tiles(scene, |tile| tile.can_fertilize())
+  }
+
+  fn start(
+    &self,
+    scene: &mut Scene,
+    _: &mut Backpack,
+    player: Entity,
+    target: Entity,
+  ) -> Option<FarmWork> {
+    let character = scene.get_components_mut::<&mut Character>(player)?;
+    let item = Item::Fertilizer(character.get_selected_fertilizer()?);
+    character.remove(&item, 1).ok()?;
+
+    Some(FarmWork::new(self.name(), target, Seconds::new(2.0)).with_item(item))
+  }
+
+  fn complete(&self, scene: &mut Scene, backpack: &mut Backpack, _: Entity, work: FarmWork) {
+    let season = match backpack.get::<DayCycle>() {
+      Some(cycle) => cycle.calendar.get_season_count(),
+      None => 0,
+    };
+
+    if let Some(Item::Fertilizer(fertilizer)) = work.item
+      && let Some(tile_entity) = work.targets.first()
+      && let Some(tile) = scene.get_components_mut::<&mut Tile>(*tile_entity)
+      && tile.can_fertilize()
+    {
+      tile.fertilized = Some(Fertilized { fertilizer, season });
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct ThrowSeed {}
+
+impl FarmAction for ThrowSeed {
+  fn name(&self) -> &'static str {
+    "ThrowSeed"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Plant Seed"
+  }
+
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Planting Seed..")
+  }
+
+  fn get_candidates(&self, scene: &mut Scene, _: Entity) -> Vec<Entity> {
+    get_tiles(scene, |tile| tile.soil.planted().is_some())
+  }
+
+  fn start(
+    &self,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    player: Entity,
+    target: Entity,
+  ) -> Option<FarmWork> {
+    let season = backpack
+      .get::<DayCycle>()
+      .map(|cycle| cycle.calendar.season);
+
+    let character = scene.get_components_mut::<&mut Character>(player)?;
+    let crop = character.get_selected_seed()?;
+    if !is_in_season(&crop, season) {
+      return None;
+    }
+    let item = Item::Seed(crop);
+    character.remove(&item, 1).ok()?;
+
+    Some(FarmWork::new(self.name(), target, Seconds::new(4.0)).with_item(item))
+  }
+
+  fn complete(&self, scene: &mut Scene, _: &mut Backpack, _: Entity, work: FarmWork) {
+    if let Some(Item::Seed(crop)) = work.item
+      && let Some(tile_entity) = work.get_target()
+      && let Some(entry) = crop.get_entry()
+      && let Some(stage_prefab) = entry.get_stage_prefab(&Stage::Seeds)
+      && let Some(prefabs) = scene.get_prefab_owned(&entry.prefab)
+      && let Some((mut parent, _)) = prefabs
+        .iter()
+        .cloned()
+        .find(|(prefab, _)| prefab.tag.name == entry.prefab)
+      && let Some((mut prefab, _)) = prefabs
+        .iter()
+        .cloned()
+        .find(|(prefab, _)| prefab.tag.name == stage_prefab)
+      && let Some(transform) = scene
+        .get_components_mut::<&TransformComponent>(tile_entity)
+        .cloned()
+      && let Some(tile) = scene
+        .get_components_mut::<&IdComponent>(tile_entity)
+        .map(|id| **id)
+      && change_soil(scene, tile_entity, Soil::planted)
+    {
+      let crop_entity = scene.create_raw_entity(&format!("{:} Crop", entry.display_name));
+      prefab.transform = transform;
+      prefab.remove::<ParentComponent>();
+      if let Some(data) = parent.get_mut::<Crop>() {
+        data.crop = crop;
+        data.tile = Some(tile);
+      }
+      scene.create_with_prefab(crop_entity, parent);
+      scene.create_with_prefab(crop_entity, prefab);
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct Harvest {}
+
+impl FarmAction for Harvest {
+  fn name(&self) -> &'static str {
+    "Harvest"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Harvest"
+  }
+
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Harvesting...")
+  }
+
+  fn get_color(&self) -> Vector3<f32> {
+    Vector3::new(1.0, 0.0, 1.0)
+  }
+
+  fn get_candidates(&self, scene: &mut Scene, _: Entity) -> Vec<Entity> {
+    let mut crops = vec![];
+    for (entity, _) in scene.query_mut::<&Harvestable>() {
+      crops.push(entity);
+    }
+    crops
+  }
+
+  fn start(&self, _: &mut Scene, _: &mut Backpack, _: Entity, target: Entity) -> Option<FarmWork> {
+    Some(FarmWork::new(self.name(), target, Seconds::new(4.0)))
+  }
+
+  fn complete(&self, scene: &mut Scene, _: &mut Backpack, player: Entity, work: FarmWork) {
+    let harvesting_entity = match work.get_target() {
+      Some(entity) => entity,
+      None => return,
+    };
+
+    let player_transform = match scene.get_components_mut::<&TransformComponent>(player) {
+      Some(transform) => transform.clone(),
+      None => return,
+    };
+
+    let mut is_showoff = false;
+    let mut overflow = None;
+
+    let (crop, crop_transform) =
+      match scene.get_components_mut::<(&Crop, &TransformComponent)>(harvesting_entity) {
+        Some((data, transform)) => (data.clone(), transform.clone()),
+        None => return,
+      };
+
+    let entry = match crop.crop.get_entry() {
+      Some(entry) => entry,
+      None => return,
+    };
+
+    // Rotten crops only leave compost behind, and withered ones nothing at all
+    let (item, award) = match crop.stage {
+      Stage::Rotten => (
+        Item::Material(MaterialType(String::from("Compost"))),
+        entry.compost,
+      ),
+      Stage::Withered => (Item::Nothing, 0),
+      _ => (
+        Item::Crop(crop.crop.clone(), crop.get_quality()),
+        entry.award,
+      ),
+    };
+
+    if award > 0
+      && let Some((character, state)) =
+        scene.get_components_mut::<(&mut Character, &mut CharacterState)>(player)
+    {
+      match character.award(item.clone(), award) {
+        Ok(is_new) => is_showoff = is_new,
+        // Whatever does not fit in the inventory is left on the ground where
+        // the crop used to be
+        Err(InventoryError::Full { item, remaining }) => overflow = Some((item, remaining)),
+        Err(error) => log::warn!("harvest failed: {:}", error),
+      }
+
+      is_showoff = is_showoff && matches!(item, Item::Crop(..));
+      if is_showoff {
+        *state = CharacterState::ShowingOff { item };
+      }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some((item, remaining)) = overflow {
+      spawn_drop(scene, item, remaining, &crop_transform);
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = (overflow, crop_transform);
+
+    // Regrowing plants go back to flowering for another harvest, everything
+    // else is cleared off the tile
+    match entry.regrows && crop.stage == Stage::Mature {
+      true => {
+        if let Some(data) = scene.get_components_mut::<&mut Crop>(harvesting_entity) {
+          *data = Crop {
+            phase_timing: Seconds::new(0.0),
+            stages_grown: 0,
+            stages_watered: 0,
+            dried_out: false,
+            dry_days: 0,
+            boost: 0,
+            ..crop.clone()
+          };
+        }
+        change_stage(scene, harvesting_entity, Stage::Flowering);
+      }
+      false => {
+        let _ = scene.despawn(harvesting_entity);
+        if let Some(tile) = crop.tile
+          && let Some(tile_entity) = find_tile(scene, tile)
+        {
+          change_soil(scene, tile_entity, Soil::cleared);
+        }
+      }
+    }
+
+    if is_showoff
+      && let Some(stage_prefab) = entry.get_stage_prefab(&Stage::Display)
+      && let Some(prefabs) = scene.get_prefab_owned(&entry.prefab)
+      && let Some((mut parent, _)) = prefabs
+        .iter()
+        .cloned()
+        .find(|(prefab, _)| prefab.tag.name == entry.prefab)
+      && let Some((mut prefab, _)) = prefabs
+        .iter()
+        .cloned()
+        .find(|(prefab, _)| prefab.tag.name == stage_prefab)
+    {
+      let crop_entity = scene.create_raw_entity("Display");
+
+      prefab.transform = player_transform;
+      prefab.transform.translation -= Vector3::z();
+      prefab.transform.translation -= Vector3::y() * 0.25;
+
+      prefab.remove::<ParentComponent>();
+      // Silver and gold crops are tinted so the tier shows while showing off
+      if let Some(color) = crop.get_quality().get_color()
+        && let Some(model) = prefab.get_mut::<ModelComponent>()
+      {
+        model.color = color;
+        model.color_intensity = 0.5;
+      }
+      if let Some(data) = parent.get_mut::<Crop>() {
+        *data = Crop {
+          stage: Stage::Display,
+          ..crop.clone()
+        };
+      }
+      scene.create_with_prefab(crop_entity, parent);
+      scene.create_with_prefab(crop_entity, prefab);
+      scene.add_local_component(crop_entity, Showoff);
+    }
+  }
+}
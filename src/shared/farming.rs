@@ -0,0 +1,427 @@
+use crate::shared::components::{
+  ActionTypes, Character, CharacterState, FarmWork, InteractionTarget, Movement,
+};
+use crate::shared::game_input::{GameInput, InputState};
+use engine::{
+  application::scene::{Scene, TransformComponent},
+  nalgebra::Vector3,
+  systems::{Backpack, Initializable, Inventory, System},
+  Entity,
+};
+use std::sync::{Arc, RwLock};
+
+#[cfg(target_arch = "wasm32")]
+use crate::client::browser::Message;
+#[cfg(target_arch = "wasm32")]
+use crate::shared::components::Tile;
+#[cfg(target_arch = "wasm32")]
+use engine::{
+  application::components::{ModelComponent, SelfComponent},
+  systems::browser::BrowserController,
+};
+
+static FARM_ACTIONS: RwLock<Vec<Arc<dyn FarmAction>>> = RwLock::new(Vec::new());
+
+// How far from a player their interaction target can be
+const REACH: f32 = 1.5;
+
+/// Something a player does to the farm by pressing the action button, like
+/// tilling or harvesting. Every action follows the same steps: it picks a
+/// target in reach, starts working on it when the player can, and applies its
+/// result once the work is done.
+pub trait FarmAction: Send + Sync {
+  /// Name the action is registered under, and what `ActionTypes` holds while
+  /// the action is selected
+  fn name(&self) -> &'static str;
+
+  /// Shown to the player while the action is selected
+  fn get_label(&self) -> &'static str;
+
+  /// Shown to the player while they work on it
+  fn get_progress_label(&self, work: &FarmWork) -> String;
+
+  /// Whether players pick the action from their selection. Actions that are
+  /// not selectable are done whenever their target is in reach.
+  fn is_selectable(&self) -> bool {
+    true
+  }
+
+  /// Whether the action is only picked when no other candidate is in reach,
+  /// however close its own are
+  fn is_fallback(&self) -> bool {
+    false
+  }
+
+  /// Tint of the target while a player has it picked out
+  fn get_color(&self) -> Vector3<f32> {
+    Vector3::new(1.0, 1.0, 0.0)
+  }
+
+  /// Everything the action can be done on as the scene is now, wherever it
+  /// is. The player's target is picked out of these.
+  fn get_candidates(&self, scene: &mut Scene, player: Entity) -> Vec<Entity>;
+
+  /// Checks whether the player can act on `target`, one of the candidates,
+  /// using up whatever the action takes. Returns the work to be done on it, if
+  /// there is any.
+  fn start(
+    &self,
+    scene: &mut Scene,
+    backpack: &mut Backpack,
+    player: Entity,
+    target: Entity,
+  ) -> Option<FarmWork>;
+
+  /// Moves the work along by a frame, returning true once it is done
+  fn progress(&self, _: &mut Scene, _: Entity, work: &mut FarmWork) -> bool {
+    work.timing.tick().is_some()
+  }
+
+  /// Applies the result of the work the player just finished
+  fn complete(&self, scene: &mut Scene, backpack: &mut Backpack, player: Entity, work: FarmWork);
+}
+
+/// Adds farm actions to the registry, in the order players cycle through them
+pub trait Access {
+  fn register();
+}
+
+impl<T: FarmAction + Default + 'static> Access for T {
+  fn register() {
+    let action = T::default();
+    let mut actions = FARM_ACTIONS.write().unwrap();
+    if actions
+      .iter()
+      .all(|registered| registered.name() != action.name())
+    {
+      actions.push(Arc::new(action));
+    }
+  }
+}
+
+pub fn get_actions() -> Vec<Arc<dyn FarmAction>> {
+  FARM_ACTIONS.read().unwrap().clone()
+}
+
+pub fn get_action(action: &ActionTypes) -> Option<Arc<dyn FarmAction>> {
+  FARM_ACTIONS
+    .read()
+    .unwrap()
+    .iter()
+    .find(|registered| action.is(registered.name()))
+    .cloned()
+}
+
+/// The action `steps` away from `action` among the selectable ones, wrapping
+/// around at either end
+fn get_selectable(action: &ActionTypes, steps: isize) -> Option<ActionTypes> {
+  let names = get_actions()
+    .iter()
+    .filter(|registered| registered.is_selectable())
+    .map(|registered| registered.name())
+    .collect::<Vec<_>>();
+  if names.is_empty() {
+    return None;
+  }
+
+  let count = names.len() as isize;
+  let index = match names.iter().position(|name| action.is(name)) {
+    Some(index) => (index as isize + steps).rem_euclid(count),
+    None => 0,
+  };
+  names
+    .get(index as usize)
+    .map(|name| ActionTypes::from(*name))
+}
+
+/// The entity the local player uses with `action` this frame, by pressing the
+/// action button while it is their target
+#[cfg(target_arch = "wasm32")]
+pub fn get_used_target(scene: &mut Scene, action: &str) -> Option<Entity> {
+  match scene.query_one::<(&GameInput, &InteractionTarget, &SelfComponent)>() {
+    Some((_, (input, target, _)))
+      if input.check(InputState::Action) && target.action.is(action) =>
+    {
+      Some(target.entity)
+    }
+    _ => None,
+  }
+}
+
+/// Offset from `position` to `target` along the ground
+fn get_offset(position: &Vector3<f32>, target: &Vector3<f32>) -> Vector3<f32> {
+  let mut offset = target - position;
+  offset.y = 0.0;
+  offset
+}
+
+/// Whether `target` is close enough for `player` to act on it, by the same
+/// reach their interaction target is picked within. The server checks the
+/// requests players send from a station window against it.
+pub fn is_in_reach(scene: &mut Scene, player: Entity, target: Entity) -> bool {
+  let position = match scene.get_components_mut::<&TransformComponent>(player) {
+    Some(transform) => transform.translation,
+    None => return false,
+  };
+
+  match scene.get_components_mut::<&TransformComponent>(target) {
+    Some(transform) => get_offset(&position, &transform.translation).norm() <= REACH,
+    None => false,
+  }
+}
+
+/// The entity of the local player, once they are in the scene
+#[cfg(target_arch = "wasm32")]
+pub fn get_local_player(scene: &mut Scene) -> Option<Entity> {
+  scene
+    .query_one::<(&Character, &SelfComponent)>()
+    .map(|(entity, _)| entity)
+}
+
+/// How well a candidate at `target` suits a player at `position` facing
+/// `direction`, lower being better. Candidates out of reach, or behind the
+/// player and not right under them, are never picked.
+fn get_score(
+  position: &Vector3<f32>,
+  direction: &Vector3<f32>,
+  target: &Vector3<f32>,
+) -> Option<f32> {
+  let offset = get_offset(position, target);
+
+  let distance = offset.norm();
+  if distance > REACH {
+    return None;
+  }
+
+  let facing = match distance > f32::EPSILON {
+    true => direction.dot(&(offset / distance)),
+    false => 1.0,
+  };
+  if facing < 0.0 && distance > 0.5 {
+    return None;
+  }
+
+  Some(distance * (2.0 - facing))
+}
+
+pub struct FarmingSystem {
+  #[cfg(target_arch = "wasm32")]
+  browser: BrowserController<Message>,
+  // Entity tinted as the target, along with the tint it had before
+  #[cfg(target_arch = "wasm32")]
+  highlighted: Option<(Entity, Vector3<f32>, f32)>,
+  #[cfg(target_arch = "wasm32")]
+  prompt: Option<String>,
+}
+
+impl Initializable for FarmingSystem {
+  #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+  fn initialize(inventory: &Inventory) -> Self {
+    #[cfg(target_arch = "wasm32")]
+    let browser = inventory.get::<BrowserController<Message>>().clone();
+    Self {
+      #[cfg(target_arch = "wasm32")]
+      browser,
+      #[cfg(target_arch = "wasm32")]
+      highlighted: None,
+      #[cfg(target_arch = "wasm32")]
+      prompt: None,
+    }
+  }
+}
+
+impl FarmingSystem {
+  pub fn handle_select_action(&self, scene: &mut Scene) {
+    for (_, (input, character)) in scene.query_mut::<(&GameInput, &mut Character)>() {
+      let steps = match (
+        input.check(InputState::ChangeActionUp),
+        input.check(InputState::ChangeActionDown),
+      ) {
+        (true, false) => 1,
+        (false, true) => -1,
+        _ => continue,
+      };
+
+      if let Some(action) = get_selectable(&character.action, steps) {
+        character.action = action;
+      }
+    }
+  }
+
+  /// Picks the one target each player would act on, out of the candidates of
+  /// the selected action and of those that need no selecting. Candidates of
+  /// fallback actions only win when nothing else is in reach.
+  pub fn handle_targets(&self, scene: &mut Scene) {
+    let mut players = vec![];
+    for (entity, (_, transform, movement, character)) in
+      scene.query_mut::<(&GameInput, &TransformComponent, &Movement, &Character)>()
+    {
+      players.push((
+        entity,
+        transform.translation,
+        movement.direction.into_inner(),
+        character.action.clone(),
+      ));
+    }
+
+    for (player_entity, position, direction, selected) in players {
+      let mut best: Option<((bool, f32), ActionTypes, Entity)> = None;
+      for action in get_actions() {
+        if action.is_selectable() && !selected.is(action.name()) {
+          continue;
+        }
+
+        for candidate in action.get_candidates(scene, player_entity) {
+          let score = match scene.get_components_mut::<&TransformComponent>(candidate) {
+            Some(transform) => get_score(&position, &direction, &transform.translation)
+              .map(|score| (action.is_fallback(), score)),
+            None => None,
+          };
+
+          if let Some(score) = score
+            && best
+              .as_ref()
+              .is_none_or(|(best_score, _, _)| score < *best_score)
+          {
+            best = Some((score, ActionTypes::from(action.name()), candidate));
+          }
+        }
+      }
+
+      let target = best.map(|(_, action, entity)| InteractionTarget { action, entity });
+      let current = scene
+        .get_components_mut::<&InteractionTarget>(player_entity)
+        .cloned();
+      if target == current {
+        continue;
+      }
+
+      match target {
+        Some(target) => scene.add_local_component(player_entity, target),
+        None => {
+          let _ = scene.remove_component::<InteractionTarget>(player_entity);
+        }
+      }
+    }
+  }
+
+  pub fn handle_start(&self, scene: &mut Scene, backpack: &mut Backpack) {
+    let mut players = vec![];
+    for (entity, (input, state, target)) in
+      scene.query_mut::<(&GameInput, &CharacterState, &InteractionTarget)>()
+    {
+      if input.check(InputState::Action)
+        && let CharacterState::Normal | CharacterState::Running = state
+      {
+        players.push((entity, target.clone()));
+      }
+    }
+
+    for (player_entity, target) in players {
+      if let Some(action) = get_action(&target.action)
+        && let Some(work) = action.start(scene, backpack, player_entity, target.entity)
+        && let Some(state) = scene.get_components_mut::<&mut CharacterState>(player_entity)
+      {
+        *state = CharacterState::Farming(work);
+      }
+    }
+  }
+
+  pub fn handle_progress(&self, scene: &mut Scene, backpack: &mut Backpack) {
+    let mut working = vec![];
+    for (entity, state) in scene.query_mut::<&CharacterState>() {
+      if let CharacterState::Farming(work) = state {
+        working.push((entity, work.clone()));
+      }
+    }
+
+    for (player_entity, mut work) in working {
+      let action = get_action(&work.action);
+      let is_done = match &action {
+        Some(action) => action.progress(scene, player_entity, &mut work),
+        None => true,
+      };
+
+      if let Some(state) = scene.get_components_mut::<&mut CharacterState>(player_entity) {
+        *state = match is_done {
+          true => CharacterState::Normal,
+          false => CharacterState::Farming(work.clone()),
+        };
+      }
+
+      if is_done && let Some(action) = action {
+        action.complete(scene, backpack, player_entity, work);
+      }
+    }
+  }
+
+  /// Tints the target of the local player, and only that one
+  #[cfg(target_arch = "wasm32")]
+  pub fn handle_highlight(&mut self, scene: &mut Scene) {
+    let target = match scene.query_one::<(&InteractionTarget, &SelfComponent)>() {
+      Some((_, (target, _))) => Some(target.clone()),
+      None => None,
+    };
+
+    // Models go back to their own tint, like the colour of a crop, while tiles
+    // get theirs back from the pickups every frame
+    if let Some((entity, color, color_intensity)) = self.highlighted
+      && target.as_ref().map(|target| target.entity) != Some(entity)
+    {
+      self.highlighted = None;
+      if let Some((model, None)) =
+        scene.get_components_mut::<(&mut ModelComponent, Option<&Tile>)>(entity)
+      {
+        model.color = color;
+        model.color_intensity = color_intensity;
+      }
+    }
+
+    if let Some(target) = target
+      && let Some(action) = get_action(&target.action)
+      && let Some(model) = scene.get_components_mut::<&mut ModelComponent>(target.entity)
+    {
+      if self.highlighted.is_none() {
+        self.highlighted = Some((target.entity, model.color, model.color_intensity));
+      }
+      model.color = action.get_color();
+      model.color_intensity = 0.1;
+    }
+  }
+
+  /// Lets the browser know what pressing the action button would do, so it can
+  /// prompt the player
+  #[cfg(target_arch = "wasm32")]
+  pub fn handle_prompt(&mut self, scene: &mut Scene) {
+    let prompt = match scene.query_one::<(&InteractionTarget, &SelfComponent)>() {
+      Some((_, (target, _))) => {
+        get_action(&target.action).map(|action| String::from(action.get_label()))
+      }
+      None => None,
+    };
+
+    if prompt != self.prompt {
+      self.browser.send(Message::UpdateInteraction {
+        prompt: prompt.clone(),
+      });
+      self.prompt = prompt;
+    }
+  }
+}
+
+impl System for FarmingSystem {
+  fn get_name(&self) -> &'static str {
+    "FarmingSystem"
+  }
+
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    self.handle_select_action(scene);
+    self.handle_targets(scene);
+    self.handle_start(scene, backpack);
+    self.handle_progress(scene, backpack);
+    #[cfg(target_arch = "wasm32")]
+    self.handle_highlight(scene);
+    #[cfg(target_arch = "wasm32")]
+    self.handle_prompt(scene);
+  }
+}
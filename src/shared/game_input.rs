@@ -1,3 +1,9 @@
+use crate::shared::catalog::Catalog;
+use crate::shared::components::InventoryCommand;
+use crate::shared::crafting::RecipeId;
+use crate::shared::shop::Purchase;
+use crate::shared::storage::ChestCommand;
+use crate::shared::trading::TradeCommand;
 use engine::systems::Backpack;
 use engine::{
   application::devices::{
@@ -5,6 +11,7 @@ use engine::{
     MouseEvent, WindowEvent,
   },
   application::input::Input,
+  application::scene::PrefabId,
   nalgebra::Vector2,
   utils::units::Seconds,
 };
@@ -28,6 +35,25 @@ pub struct GameInput {
   vertical: HashSet<KeyboardKey>,
 
   pub state: InputState,
+  pub commands: Vec<InventoryCommand>,
+  pub trades: Vec<TradeCommand>,
+  pub purchases: Vec<Purchase>,
+  pub crafts: Vec<RecipeId>,
+  pub chests: Vec<ChestCommand>,
+  // Repair stations the player asked to upgrade their watering can at
+  pub upgrades: Vec<PrefabId>,
+}
+
+/// Commands coming from the browser, kept in the backpack until the next input
+/// snapshot carries them over to the server.
+#[derive(Debug, Default)]
+pub struct PendingCommands {
+  pub inventory: Vec<InventoryCommand>,
+  pub trades: Vec<TradeCommand>,
+  pub purchases: Vec<Purchase>,
+  pub crafts: Vec<RecipeId>,
+  pub chests: Vec<ChestCommand>,
+  pub upgrades: Vec<PrefabId>,
 }
 
 bitflags! {
@@ -48,6 +74,18 @@ bitflags! {
     const Action                 = 0b00000000000000000000010000000000;
     const ChangeActionUp         = 0b00000000000000000000100000000000;
     const ChangeActionDown       = 0b00000000000000000001000000000000;
+
+    const HotbarNext             = 0b00000000000000000010000000000000;
+    const HotbarPrevious         = 0b00000000000000000100000000000000;
+    const Hotbar1                = 0b00000000000000001000000000000000;
+    const Hotbar2                = 0b00000000000000010000000000000000;
+    const Hotbar3                = 0b00000000000000100000000000000000;
+    const Hotbar4                = 0b00000000000001000000000000000000;
+    const Hotbar5                = 0b00000000000010000000000000000000;
+    const Hotbar6                = 0b00000000000100000000000000000000;
+    const Hotbar7                = 0b00000000001000000000000000000000;
+    const Hotbar8                = 0b00000000010000000000000000000000;
+    const Hotbar9                = 0b00000000100000000000000000000000;
   }
 }
 
@@ -58,6 +96,12 @@ impl GameInput {
       right: 0.0,
       forward: 0.0,
       state: InputState::Empty,
+      commands: vec![],
+      trades: vec![],
+      purchases: vec![],
+      crafts: vec![],
+      chests: vec![],
+      upgrades: vec![],
       mouse: Vector2::zeros(),
       delta: Vector2::zeros(),
       horizontal: HashSet::new(),
@@ -69,6 +113,22 @@ impl GameInput {
     self.state.contains(state)
   }
 
+  pub fn get_hotbar_slot(&self) -> Option<usize> {
+    const HOTBAR: [InputState; 9] = [
+      InputState::Hotbar1,
+      InputState::Hotbar2,
+      InputState::Hotbar3,
+      InputState::Hotbar4,
+      InputState::Hotbar5,
+      InputState::Hotbar6,
+      InputState::Hotbar7,
+      InputState::Hotbar8,
+      InputState::Hotbar9,
+    ];
+
+    HOTBAR.iter().position(|state| self.state.contains(*state))
+  }
+
   fn handle_joystick(&mut self, event: DeviceEvent) {
     // Enable joystick
     match event {
@@ -91,6 +151,26 @@ impl GameInput {
       ) => {
         self.state -= InputState::IsRunning;
       }
+      // The right bumper runs, so the left one cycles the hotbar forward and B
+      // steps back through it
+      DeviceEvent::Gamepad(
+        _,
+        GamepadEvent::Button(ButtonState::Down, GamepadButton::LeftShoulder),
+      ) => {
+        self.state |= InputState::HotbarNext;
+      }
+      DeviceEvent::Gamepad(
+        _,
+        GamepadEvent::Button(ButtonState::Up, GamepadButton::LeftShoulder),
+      ) => {
+        self.state -= InputState::HotbarNext;
+      }
+      DeviceEvent::Gamepad(_, GamepadEvent::Button(ButtonState::Down, GamepadButton::B)) => {
+        self.state |= InputState::HotbarPrevious;
+      }
+      DeviceEvent::Gamepad(_, GamepadEvent::Button(ButtonState::Up, GamepadButton::B)) => {
+        self.state -= InputState::HotbarPrevious;
+      }
 
       DeviceEvent::Gamepad(_, GamepadEvent::Button(ButtonState::Down, GamepadButton::X)) => {
         self.state |= InputState::Action;
@@ -197,6 +277,35 @@ impl GameInput {
       DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Down, KeyboardKey::Period)) => {
         self.state |= InputState::ChangeActionUp;
       }
+
+      // Hotbar
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Down, KeyboardKey::Key1)) => {
+        self.state |= InputState::Hotbar1;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Down, KeyboardKey::Key2)) => {
+        self.state |= InputState::Hotbar2;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Down, KeyboardKey::Key3)) => {
+        self.state |= InputState::Hotbar3;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Down, KeyboardKey::Key4)) => {
+        self.state |= InputState::Hotbar4;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Down, KeyboardKey::Key5)) => {
+        self.state |= InputState::Hotbar5;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Down, KeyboardKey::Key6)) => {
+        self.state |= InputState::Hotbar6;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Down, KeyboardKey::Key7)) => {
+        self.state |= InputState::Hotbar7;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Down, KeyboardKey::Key8)) => {
+        self.state |= InputState::Hotbar8;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Down, KeyboardKey::Key9)) => {
+        self.state |= InputState::Hotbar9;
+      }
       // End: Keyboard down
 
       // Start: Keyboard up
@@ -252,6 +361,35 @@ impl GameInput {
         self.state -= InputState::ChangeActionUp;
       }
 
+      // Hotbar
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Up, KeyboardKey::Key1)) => {
+        self.state -= InputState::Hotbar1;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Up, KeyboardKey::Key2)) => {
+        self.state -= InputState::Hotbar2;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Up, KeyboardKey::Key3)) => {
+        self.state -= InputState::Hotbar3;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Up, KeyboardKey::Key4)) => {
+        self.state -= InputState::Hotbar4;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Up, KeyboardKey::Key5)) => {
+        self.state -= InputState::Hotbar5;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Up, KeyboardKey::Key6)) => {
+        self.state -= InputState::Hotbar6;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Up, KeyboardKey::Key7)) => {
+        self.state -= InputState::Hotbar7;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Up, KeyboardKey::Key8)) => {
+        self.state -= InputState::Hotbar8;
+      }
+      DeviceEvent::Keyboard(KeyboardEvent::Button(ButtonState::Up, KeyboardKey::Key9)) => {
+        self.state -= InputState::Hotbar9;
+      }
+
       _ => {}
     }
   }
@@ -308,7 +446,23 @@ impl Default for GameInput {
 }
 
 impl Input for GameInput {
-  fn from_backpack(&mut self, _: &mut Backpack) {}
+  fn from_backpack(&mut self, backpack: &mut Backpack) {
+    // Clients can only predict what happens to items once the server sent them
+    // the catalog, so until then they hold off on acting and on their commands
+    if !Catalog::is_loaded() {
+      self.state -= InputState::Action;
+      return;
+    }
+
+    if let Some(pending) = backpack.get_mut::<PendingCommands>() {
+      self.commands.append(&mut pending.inventory);
+      self.trades.append(&mut pending.trades);
+      self.purchases.append(&mut pending.purchases);
+      self.crafts.append(&mut pending.crafts);
+      self.chests.append(&mut pending.chests);
+      self.upgrades.append(&mut pending.upgrades);
+    }
+  }
 
   fn reset(&mut self) {
     self.delta.x = 0.0;
@@ -318,6 +472,14 @@ impl Input for GameInput {
     self.state -= InputState::ToggleDebugPhysics;
     self.state -= InputState::ToggleDebugPerformance;
     self.state -= InputState::Escape;
+    self.state -= InputState::HotbarNext;
+    self.state -= InputState::HotbarPrevious;
+    self.commands.clear();
+    self.trades.clear();
+    self.purchases.clear();
+    self.crafts.clear();
+    self.chests.clear();
+    self.upgrades.clear();
   }
 
   fn has_mouse_lock(&self) -> bool {
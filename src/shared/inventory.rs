@@ -0,0 +1,60 @@
+use crate::shared::components::Character;
+use crate::shared::game_input::{GameInput, InputState};
+use engine::{
+  application::scene::Scene,
+  systems::{Backpack, Initializable, Inventory, System},
+};
+
+pub struct InventorySystem {}
+
+impl Initializable for InventorySystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {}
+  }
+}
+
+impl InventorySystem {
+  pub fn handle_hotbar(&self, scene: &mut Scene) {
+    for (_, (input, character)) in scene.query_mut::<(&GameInput, &mut Character)>() {
+      let size = character.get_hotbar_size();
+      if size == 0 {
+        continue;
+      }
+
+      if let Some(slot) = input.get_hotbar_slot() {
+        let _ = character.select_slot(slot);
+      }
+      if input.check(InputState::HotbarNext) {
+        character.selected = (character.selected + 1) % size;
+      }
+      if input.check(InputState::HotbarPrevious) {
+        character.selected = (character.selected + size - 1) % size;
+      }
+    }
+  }
+
+  // Slot operations are only applied by the server, clients receive the
+  // resulting inventory along with the rest of the `Character`
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn handle_commands(&self, scene: &mut Scene) {
+    for (_, (input, character)) in scene.query_mut::<(&GameInput, &mut Character)>() {
+      for command in &input.commands {
+        if let Err(error) = character.apply(command) {
+          log::warn!("rejected inventory command {:?}: {:}", command, error);
+        }
+      }
+    }
+  }
+}
+
+impl System for InventorySystem {
+  fn get_name(&self) -> &'static str {
+    "InventorySystem"
+  }
+
+  fn run(&mut self, scene: &mut Scene, _: &mut Backpack) {
+    self.handle_hotbar(scene);
+    #[cfg(not(target_arch = "wasm32"))]
+    self.handle_commands(scene);
+  }
+}
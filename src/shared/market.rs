@@ -0,0 +1,212 @@
+use crate::shared::catalog::Catalog;
+use crate::shared::components::{CropType, Item};
+use engine::{
+  application::scene::Scene,
+  systems::{trusty::MultiplayerController, Backpack, Initializable, Inventory, System},
+  tsify,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tagged::registry::Prev;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::shared::state_machine::StateMachine;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::shared::timeofday::DayCycle;
+#[cfg(not(target_arch = "wasm32"))]
+use engine::ConnectionId;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::HashSet;
+
+#[cfg(target_arch = "wasm32")]
+use crate::client::browser::Message;
+#[cfg(target_arch = "wasm32")]
+use engine::systems::browser::BrowserController;
+
+// Amount of a crop sold recently at which its price drops to half
+const SATURATION: f32 = 100.0;
+// Share of the recent supply that is still remembered the next day
+const RECOVERY: f32 = 0.5;
+const MIN_MULTIPLIER: f32 = 0.25;
+const HISTORY_DAYS: usize = 7;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, tsify::Tsify)]
+pub struct MarketPrice {
+  pub base: u64,
+  pub current: u64,
+  pub supply: f32,
+  // Closing price of the last few days, oldest first
+  pub history: Vec<u64>,
+}
+
+impl MarketPrice {
+  pub fn new(base: u64) -> Self {
+    Self {
+      base,
+      current: base,
+      supply: 0.0,
+      history: vec![],
+    }
+  }
+
+  fn update(&mut self) {
+    let multiplier = (1.0 / (1.0 + self.supply / SATURATION)).max(MIN_MULTIPLIER);
+    self.current = (self.base as f32 * multiplier).round() as u64;
+  }
+}
+
+/// Crop prices for the whole session. Every sale pushes the price of that crop
+/// down, and prices slowly recover as in-game days go by.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, tsify::Tsify)]
+pub struct Market {
+  pub prices: HashMap<CropType, MarketPrice>,
+}
+
+impl Market {
+  pub fn new() -> Self {
+    let prices = Catalog::get()
+      .crops
+      .iter()
+      .map(|(crop, entry)| (crop.clone(), MarketPrice::new(entry.sell_price)))
+      .collect();
+
+    Self { prices }
+  }
+
+  pub fn get_price(&self, item: &Item) -> Option<u64> {
+    match item {
+      Item::Crop(crop, quality) => match self.prices.get(crop) {
+        Some(price) => Some(quality.apply_to_price(price.current)),
+        None => item.get_price(),
+      },
+      Item::Material(_) | Item::Fertilizer(_) => item.get_price(),
+      Item::Nothing | Item::Seed(_) | Item::Tool(_) => None,
+    }
+  }
+
+  pub fn record_sale(&mut self, item: &Item, quantity: usize) {
+    if let Item::Crop(crop, _) = item
+      && let Some(price) = self.prices.get_mut(crop)
+    {
+      price.supply += quantity as f32;
+      price.update();
+    }
+  }
+
+  pub fn end_day(&mut self) {
+    for price in self.prices.values_mut() {
+      price.history.push(price.current);
+      if price.history.len() > HISTORY_DAYS {
+        price.history.remove(0);
+      }
+
+      price.supply *= RECOVERY;
+      price.update();
+    }
+  }
+}
+
+pub struct MarketSystem {
+  multiplayer: MultiplayerController,
+  // Players the market was last sent to
+  #[cfg(not(target_arch = "wasm32"))]
+  connections: HashSet<ConnectionId>,
+  #[cfg(target_arch = "wasm32")]
+  browser: BrowserController<Message>,
+}
+
+impl Initializable for MarketSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let multiplayer = inventory.get::<MultiplayerController>().clone();
+    #[cfg(target_arch = "wasm32")]
+    let browser = inventory.get::<BrowserController<Message>>().clone();
+    Self {
+      multiplayer,
+      #[cfg(not(target_arch = "wasm32"))]
+      connections: HashSet::new(),
+      #[cfg(target_arch = "wasm32")]
+      browser,
+    }
+  }
+}
+
+impl MarketSystem {
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn handle_new_day(&mut self, backpack: &mut Backpack) {
+    if let Some((DayCycle { is_new_day, .. }, market)) = backpack.fetch_mut::<(DayCycle, Market)>()
+      && *is_new_day
+    {
+      market.end_day();
+    }
+  }
+
+  /// Sends the market out whenever it changes, and to everyone when a player
+  /// joins so they don't wait for the next sale to see current prices
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn handle_replicate(&mut self, backpack: &mut Backpack) {
+    let connections = match backpack.get::<StateMachine>() {
+      Some(machine) => machine.players.iter().map(|(id, _, _)| *id).collect(),
+      None => HashSet::new(),
+    };
+    let has_joined = connections.iter().any(|id| !self.connections.contains(id));
+    self.connections = connections;
+
+    if let Some((market, Prev(prev))) = backpack.fetch_mut::<(Market, Prev<Market>)>() {
+      if market != prev || has_joined {
+        self.multiplayer.broadcast_custom(market.clone());
+      }
+    }
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  pub fn handle_receive_from_server(&mut self, backpack: &mut Backpack) {
+    while let Ok(market) = self.multiplayer.try_recv_custom::<Market>() {
+      backpack.insert(market);
+    }
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  pub fn handle_replicate(&mut self, backpack: &mut Backpack) {
+    if let Some((market, Prev(prev))) = backpack.fetch_mut::<(Market, Prev<Market>)>() {
+      if market != prev {
+        self.browser.send(Message::UpdateMarket {
+          market: market.clone(),
+        });
+      }
+    }
+  }
+
+  pub fn handle_prev(&mut self, backpack: &mut Backpack) {
+    if let Some((curr, Prev(prev))) = backpack.fetch_mut::<(Market, Prev<Market>)>() {
+      *prev = curr.clone();
+    }
+  }
+}
+
+impl System for MarketSystem {
+  fn get_name(&self) -> &'static str {
+    "MarketSystem"
+  }
+
+  fn attach(&mut self, _scene: &mut Scene, backpack: &mut Backpack) {
+    let market = Market::new();
+    // The browser only hears about changes from here on, so it starts out with
+    // the prices as they are now
+    #[cfg(target_arch = "wasm32")]
+    self.browser.send(Message::UpdateMarket {
+      market: market.clone(),
+    });
+    backpack.insert(market.clone());
+    backpack.insert(Prev(market));
+  }
+
+  fn run(&mut self, _scene: &mut Scene, backpack: &mut Backpack) {
+    #[cfg(not(target_arch = "wasm32"))]
+    self.handle_new_day(backpack);
+    #[cfg(target_arch = "wasm32")]
+    self.handle_receive_from_server(backpack);
+
+    self.handle_replicate(backpack);
+    self.handle_prev(backpack);
+  }
+}
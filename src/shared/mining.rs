@@ -0,0 +1,189 @@
+use crate::shared::components::{Character, Durability, FarmWork, Item, Rock, ToolType};
+use crate::shared::farming::FarmAction;
+use crate::shared::tools::use_tool;
+use engine::{
+  application::scene::{Scene, TransformComponent},
+  nalgebra::Vector3,
+  systems::{Backpack, Initializable, Inventory, System},
+  utils::units::Seconds,
+  Entity,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::shared::pickups::spawn_drop;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::shared::timeofday::DayCycle;
+
+/// A rock that was mined out, waiting for its day to come back
+#[derive(Clone)]
+pub struct BrokenRock {
+  pub rock: Rock,
+  pub transform: TransformComponent,
+  pub respawn_day: u32,
+}
+
+/// Rocks broken by the players, kept by the server until they respawn
+#[derive(Clone, Default)]
+pub struct Quarry {
+  pub broken: Vec<BrokenRock>,
+}
+
+/// Hits a rock with the pickaxe the player is holding, breaking it once it
+/// took enough hits
+#[derive(Default)]
+pub struct Mine {}
+
+impl FarmAction for Mine {
+  fn name(&self) -> &'static str {
+    "Mine"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Mine Rock"
+  }
+
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Mining...")
+  }
+
+  fn is_selectable(&self) -> bool {
+    false
+  }
+
+  fn get_color(&self) -> Vector3<f32> {
+    Vector3::new(0.6, 0.6, 0.6)
+  }
+
+  fn get_candidates(&self, scene: &mut Scene, player: Entity) -> Vec<Entity> {
+    match scene.get_components_mut::<&Character>(player) {
+      Some(character) if character.is_holding(&Item::Tool(ToolType::Pickaxe)) => {}
+      _ => return vec![],
+    }
+
+    let mut rocks = vec![];
+    for (entity, _) in scene.query_mut::<&Rock>() {
+      rocks.push(entity);
+    }
+    rocks
+  }
+
+  fn start(
+    &self,
+    scene: &mut Scene,
+    _: &mut Backpack,
+    player: Entity,
+    target: Entity,
+  ) -> Option<FarmWork> {
+    let (character, durability) =
+      scene.get_components_mut::<(&Character, Option<&mut Durability>)>(player)?;
+    if !character.is_holding(&Item::Tool(ToolType::Pickaxe)) {
+      return None;
+    }
+    use_tool(durability, ToolType::Pickaxe).ok()?;
+
+    Some(FarmWork::new(self.name(), target, Seconds::new(1.0)))
+  }
+
+  // Only the server breaks rocks, clients see them go away once it despawns
+  // them
+  #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+  fn complete(&self, scene: &mut Scene, backpack: &mut Backpack, _: Entity, work: FarmWork) {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(rock_entity) = work.get_target() {
+      let (rock, transform) =
+        match scene.get_components_mut::<(&mut Rock, &TransformComponent)>(rock_entity) {
+          Some((rock, transform)) => {
+            if !rock.hit() {
+              return;
+            }
+            (rock.clone(), transform.clone())
+          }
+          None => return,
+        };
+
+      let day = match backpack.get::<DayCycle>() {
+        Some(cycle) => cycle.day,
+        None => 0,
+      };
+
+      for drop in &rock.drops {
+        spawn_drop(scene, drop.item.clone(), drop.quantity, &transform);
+      }
+
+      if let Some(quarry) = backpack.get_mut::<Quarry>() {
+        quarry.broken.push(BrokenRock {
+          respawn_day: day + rock.respawn_days,
+          rock,
+          transform,
+        });
+      }
+
+      let _ = scene.despawn(rock_entity);
+    }
+  }
+}
+
+/// Brings broken rocks back once their day comes
+pub struct MiningSystem {}
+
+impl Initializable for MiningSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {}
+  }
+}
+
+impl MiningSystem {
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn handle_respawn(&self, scene: &mut Scene, backpack: &mut Backpack) {
+    let day = match backpack.get::<DayCycle>() {
+      Some(DayCycle {
+        day,
+        is_new_day: true,
+        ..
+      }) => *day,
+      _ => return,
+    };
+
+    let quarry = match backpack.get_mut::<Quarry>() {
+      Some(quarry) => quarry,
+      None => return,
+    };
+
+    let (ready, waiting) = quarry
+      .broken
+      .drain(..)
+      .partition::<Vec<_>, _>(|broken| broken.respawn_day <= day);
+    quarry.broken = waiting;
+
+    for BrokenRock {
+      mut rock,
+      transform,
+      ..
+    } in ready
+    {
+      rock.health.current = rock.health.max;
+      scene.spawn_prefab_with("Prefab::Rock", |prefab| {
+        prefab.transform = transform.clone();
+        if let Some(data) = prefab.get_mut::<Rock>() {
+          *data = rock.clone();
+        }
+      });
+    }
+  }
+}
+
+impl System for MiningSystem {
+  fn get_name(&self) -> &'static str {
+    "MiningSystem"
+  }
+
+  fn attach(&mut self, _scene: &mut Scene, backpack: &mut Backpack) {
+    backpack.insert(Quarry::default());
+  }
+
+  #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    #[cfg(not(target_arch = "wasm32"))]
+    self.handle_respawn(scene, backpack);
+  }
+}
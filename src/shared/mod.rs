@@ -1,10 +1,24 @@
 pub mod animations;
+pub mod catalog;
+pub mod chopping;
 pub mod collision;
 pub mod components;
+pub mod crafting;
+pub mod farm_actions;
+pub mod farming;
 pub mod game_input;
 pub mod inputs;
+pub mod inventory;
 pub mod loading;
+pub mod market;
+pub mod mining;
 pub mod pickups;
+pub mod placement;
+pub mod shop;
+pub mod sprinklers;
 pub mod state_machine;
+pub mod storage;
 pub mod timeofday;
+pub mod tools;
+pub mod trading;
 pub mod ui_components;
@@ -1,278 +1,351 @@
 use crate::shared::components::{
-  Action, ActionTypes, Character, CharacterState, Crop, CropTile, CropType, Harvestable,
-  Inventory as GameInventory, Item, Level, Log, Pickup, PickupSpace, Quantity, SalesBin, Seeds,
-  Stage, Tile, TimeOfDay, WaterCan, WaterSource, WateredTile,
+  Character, CharacterState, Crop, CropType, FarmWork, Harvestable, InteractionTarget,
+  Inventory as GameInventory, InventoryError, InventoryItem, Item, Log, Pickup, PickupSpace,
+  Quantity, SalesBin, Soil, Stage, Tile, TimeOfDay,
 };
+use crate::shared::farming::FarmAction;
 use crate::shared::game_input::{GameInput, InputState};
+use crate::shared::market::Market;
 use crate::shared::state_machine::{GameState, StateMachine};
+use crate::shared::timeofday::{DayCycle, Season};
 use crate::shared::ui_components::InventoryDisplay;
 use engine::{
   application::{
     components::{
-      LightComponent, ModelComponent, NetworkedPlayerComponent, ParentComponent, SelfComponent,
-      TextComponent,
+      LightComponent, ModelComponent, NetworkedPlayerComponent, ParentComponent, PhysicsComponent,
+      SelfComponent, TextComponent,
     },
-    scene::{Collision, CollisionEnter, CollisionExit, IdComponent, Scene, TransformComponent},
+    scene::{IdComponent, PrefabId, Scene, TransformComponent},
   },
   nalgebra::Vector3,
-  systems::{Backpack, Initializable, Inventory, System},
+  systems::{trusty::MultiplayerController, Backpack, Initializable, Inventory, System},
+  tsify,
   utils::units::{Framerate, Radians, Seconds},
+  Entity,
 };
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::f32::consts::PI;
 
-pub struct Showoff;
+#[cfg(target_arch = "wasm32")]
+use crate::client::browser::Message;
+#[cfg(target_arch = "wasm32")]
+use engine::systems::browser::BrowserController;
 
-pub struct PickupsSystem {}
+pub struct Showoff;
 
-impl Initializable for PickupsSystem {
-  fn initialize(inventory: &Inventory) -> Self {
-    Self {}
+/// Whether `crop` can be planted and grown in `season`. Crops are never out of
+/// season before the calendar is known.
+pub fn is_in_season(crop: &CropType, season: Option<Season>) -> bool {
+  match (crop.get_entry(), season) {
+    (Some(entry), Some(season)) => entry.is_in_season(season),
+    (Some(_), None) => true,
+    (None, _) => false,
   }
 }
 
-impl PickupsSystem {
-  pub fn handle_select_action(&self, scene: &mut Scene) {
-    for (_, (input, character)) in scene.query_mut::<(&GameInput, &mut Character)>() {
-      if input.check(InputState::ChangeActionUp) {
-        character.action = match character.action {
-          ActionTypes::WaterTile => ActionTypes::ThrowSeed,
-          ActionTypes::ThrowSeed => ActionTypes::Harvest,
-          ActionTypes::Harvest => ActionTypes::WaterTile,
-        };
+/// Moves a tile's soil on to its next state and swaps in the model showing it.
+/// Returns false, leaving the tile alone, when the change does not apply to the
+/// state its soil is in.
+pub fn change_soil(scene: &mut Scene, entity: Entity, change: fn(&Soil) -> Option<Soil>) -> bool {
+  let soil = match scene.get_components_mut::<&mut Tile>(entity) {
+    Some(tile) => match change(&tile.soil) {
+      Some(soil) => {
+        tile.soil = soil;
+        soil
       }
-      if input.check(InputState::ChangeActionDown) {
-        character.action = match character.action {
-          ActionTypes::WaterTile => ActionTypes::Harvest,
-          ActionTypes::Harvest => ActionTypes::ThrowSeed,
-          ActionTypes::ThrowSeed => ActionTypes::WaterTile,
-        };
-      }
-    }
+      None => return false,
+    },
+    None => return false,
+  };
+
+  if let Some(prefab) = scene.get_parent_prefab_owned(soil.get_prefab())
+    && let Some(model) = prefab.get::<ModelComponent>()
+  {
+    scene.add_component(entity, model.clone());
   }
+  true
+}
 
-  pub fn handle_pickup(&self, scene: &mut Scene) {
-    let mut spaces = HashMap::new();
-    for (_, (id, network, _)) in
-      scene.query_mut::<(&IdComponent, &NetworkedPlayerComponent, &PickupSpace)>()
-    {
-      spaces.insert(*network.connection_id, *id);
+/// Finds the entity of the tile with the given id
+pub fn find_tile(scene: &mut Scene, tile: PrefabId) -> Option<Entity> {
+  for (entity, (id, _)) in scene.query_mut::<(&IdComponent, &Tile)>() {
+    if **id == tile {
+      return Some(entity);
     }
+  }
+  None
+}
 
-    let mut insertions = vec![];
-    //for (_, (log, _)) in scene.query_mut::<(&mut Log, &Collision<Action, Pickup>)>() {
-    for (_, (id, input, network, collision)) in scene.query_mut::<(
-      &IdComponent,
-      &GameInput,
-      &NetworkedPlayerComponent,
-      &Collision<Action, Pickup>,
-    )>() {
-      if input.check(InputState::Action)
-        && let Some(id) = spaces.get(&network.connection_id)
-      {
-        //pickups.insert(network.connection_id, *id, collision.other);
-        insertions.push((collision.other, *id));
-      }
-    }
+/// Moves a crop on to `stage` in place, taking the model, collider and
+/// harvestability from that stage's prefab. The crop keeps its entity and
+/// everything it tracked through the earlier stages.
+pub fn change_stage(scene: &mut Scene, entity: Entity, stage: Stage) {
+  let crop = match scene.get_components_mut::<&mut Crop>(entity) {
+    Some(crop) => {
+      crop.stage = stage.clone();
+      crop.crop.clone()
+    }
+    None => return,
+  };
+
+  let prefab = match crop.get_entry() {
+    Some(entry) => match (
+      entry.get_stage_prefab(&stage),
+      scene.get_prefab_owned(&entry.prefab),
+    ) {
+      (Some(stage_prefab), Some(prefabs)) => prefabs
+        .into_iter()
+        .find(|(prefab, _)| prefab.tag.name == stage_prefab)
+        .map(|(prefab, _)| prefab),
+      _ => None,
+    },
+    None => None,
+  };
+  let prefab = match prefab {
+    Some(prefab) => prefab,
+    None => return,
+  };
+
+  if let Some(model) = prefab.get::<ModelComponent>() {
+    scene.add_component(entity, model.clone());
+  }
+  if let Some(physics) = prefab.get::<PhysicsComponent>() {
+    scene.add_component(entity, physics.clone());
+  } else {
+    let _ = scene.remove_component::<PhysicsComponent>(entity);
+  }
+  if let Some(harvestable) = prefab.get::<Harvestable>() {
+    scene.add_component(entity, harvestable.clone());
+  } else {
+    let _ = scene.remove_component::<Harvestable>(entity);
+  }
+}
 
-    for (entity, parent_id) in insertions {
-      scene.add_component(entity, ParentComponent::new(*parent_id));
-      scene.add_component(entity, TransformComponent::default());
-    }
+/// Drops `quantity` of `item` on the ground as a pickup that any player can
+/// collect into their inventory.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn spawn_drop(scene: &mut Scene, item: Item, quantity: usize, transform: &TransformComponent) {
+  scene.spawn_prefab_with("Prefab::Drop", |prefab| {
+    let mut transform = transform.clone();
+    transform.translation.y += 0.25;
+    transform.scale = Vector3::new(0.5, 0.5, 0.5);
+
+    prefab.transform = transform;
+    if let Some(pickup) = prefab.get_mut::<Pickup>() {
+      pickup.item = Some(InventoryItem {
+        item: item.clone(),
+        quantity: Quantity::Finite(quantity),
+      });
+    }
+  });
+}
 
-    for (_, (model, _, maybe_collision)) in
-      scene.query_mut::<(&mut ModelComponent, &Tile, Option<&Collision<Action, Tile>>)>()
-    {
-      if let Some(_) = maybe_collision {
-        model.color = Vector3::new(1.0, 1.0, 0.0);
-        model.color_intensity = 0.1;
-      } else {
-        model.color_intensity = 0.0;
-      }
-    }
+/// Sent by the server to every client once a sales bin pays out at the end of
+/// the day. Clients only forward the reports that belong to their own player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, tsify::Tsify)]
+pub struct SalesReport {
+  pub seller: PrefabId,
+  pub items: Vec<InventoryItem>,
+  pub cash: u64,
+}
+
+/// Picks up whatever lies on the ground, or carries it when it is not an item
+#[derive(Default)]
+pub struct Collect {}
+
+impl FarmAction for Collect {
+  fn name(&self) -> &'static str {
+    "Collect"
   }
 
-  pub fn handle_water_sources(&self, scene: &mut Scene, backpack: &mut Backpack) {
-    let delta_time = backpack.get::<Seconds>().unwrap();
+  fn get_label(&self) -> &'static str {
+    "Collect"
+  }
 
-    for (_, (input, character, can, collision)) in scene.query_mut::<(
-      &GameInput,
-      &mut CharacterState,
-      &mut WaterCan,
-      &Collision<Action, WaterSource>,
-    )>() {
-      if input.check(InputState::Action)
-        && let CharacterState::Normal | CharacterState::Running = character
-      {
-        *character = CharacterState::CollectingWater;
-        can.level.maximize_with_rate(0.125);
-      }
-    }
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Picking up..")
+  }
 
-    for (_, (input, character, can)) in
-      scene.query_mut::<(&GameInput, &mut CharacterState, &mut WaterCan)>()
-    {
-      if let CharacterState::CollectingWater = character {
-        if let Some(_) = can.level.tick() {
-          *character = CharacterState::Normal;
-        }
+  fn is_selectable(&self) -> bool {
+    false
+  }
+
+  // Pickups already carried by someone are out of reach for everyone
+  fn get_candidates(&self, scene: &mut Scene, _: Entity) -> Vec<Entity> {
+    let mut pickups = vec![];
+    for (entity, (_, parent)) in scene.query_mut::<(&Pickup, Option<&ParentComponent>)>() {
+      if parent.is_none() {
+        pickups.push(entity);
       }
     }
+    pickups
+  }
 
-    for (_, (model, _, _)) in scene.query_mut::<(
-      &mut ModelComponent,
-      &WaterSource,
-      &CollisionEnter<Action, WaterSource>,
-    )>() {
-      model.color = Vector3::new(0.0, 0.0, 1.0);
-      model.color_intensity = 0.1;
+  // There is no work to it, the pickups system collects it right away
+  fn start(&self, _: &mut Scene, _: &mut Backpack, _: Entity, _: Entity) -> Option<FarmWork> {
+    None
+  }
+
+  fn complete(&self, _: &mut Scene, _: &mut Backpack, _: Entity, _: FarmWork) {}
+}
+
+/// Drops every crop the player can sell into the sales bin
+#[derive(Default)]
+pub struct Sell {}
+
+impl FarmAction for Sell {
+  fn name(&self) -> &'static str {
+    "Sell"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Sell Crops"
+  }
+
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Selling..")
+  }
+
+  fn is_selectable(&self) -> bool {
+    false
+  }
+
+  fn get_color(&self) -> Vector3<f32> {
+    Vector3::new(0.0, 0.0, 1.0)
+  }
+
+  fn get_candidates(&self, scene: &mut Scene, _: Entity) -> Vec<Entity> {
+    let mut bins = vec![];
+    for (entity, _) in scene.query_mut::<&SalesBin>() {
+      bins.push(entity);
     }
+    bins
+  }
 
-    for (_, (model, _, _)) in scene.query_mut::<(
-      &mut ModelComponent,
-      &WaterSource,
-      &CollisionExit<Action, WaterSource>,
-    )>() {
-      model.color_intensity = 0.0;
+  // There is no work to it, the pickups system takes the crops right away
+  fn start(&self, _: &mut Scene, _: &mut Backpack, _: Entity, _: Entity) -> Option<FarmWork> {
+    None
+  }
+
+  fn complete(&self, _: &mut Scene, _: &mut Backpack, _: Entity, _: FarmWork) {}
+}
+
+pub struct PickupsSystem {
+  multiplayer: MultiplayerController,
+  #[cfg(target_arch = "wasm32")]
+  browser: BrowserController<Message>,
+}
+
+impl Initializable for PickupsSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let multiplayer = inventory.get::<MultiplayerController>().clone();
+    #[cfg(target_arch = "wasm32")]
+    let browser = inventory.get::<BrowserController<Message>>().clone();
+    Self {
+      multiplayer,
+      #[cfg(target_arch = "wasm32")]
+      browser,
     }
   }
+}
 
-  pub fn handle_watering_tiles(&self, scene: &mut Scene, backpack: &mut Backpack) {
-    let delta_time = backpack.get::<Seconds>().unwrap();
-
-    for (_, (input, character, state, can, collision)) in scene
-      .query_mut::<(
-        &GameInput,
-        &mut Character,
-        &mut CharacterState,
-        &mut WaterCan,
-        &Collision<Action, Tile>,
-      )>()
-      .without::<WateredTile>()
+impl PickupsSystem {
+  pub fn handle_pickup(&self, scene: &mut Scene) {
+    let mut spaces = HashMap::new();
+    for (_, (id, network, _)) in
+      scene.query_mut::<(&IdComponent, &NetworkedPlayerComponent, &PickupSpace)>()
     {
-      if input.check(InputState::Action)
-        && let ActionTypes::WaterTile = character.action
-        && can.level.current >= 1.0
-        && let CharacterState::Normal | CharacterState::Running = state
-      {
-        *state = CharacterState::WorkingTile(collision.other);
-        can.level.change_by(-1.0, Seconds::new(4.0));
-      }
+      spaces.insert(*network.connection_id, *id);
     }
 
-    let mut working_tile = None;
-    for (_, (input, character, can)) in
-      scene.query_mut::<(&GameInput, &mut CharacterState, &mut WaterCan)>()
-    {
-      if let CharacterState::WorkingTile(entity) = character {
-        if let Some(_) = can.level.tick() {
-          working_tile = Some(*entity);
-          *character = CharacterState::Normal;
-        }
+    let mut drops = HashMap::new();
+    for (entity, pickup) in scene.query_mut::<&Pickup>() {
+      if let Some(item) = &pickup.item {
+        drops.insert(entity, item.clone());
       }
     }
 
+    let mut insertions = vec![];
+    let mut collections = vec![];
+    for (player_entity, (input, network, target)) in
+      scene.query_mut::<(&GameInput, &NetworkedPlayerComponent, &InteractionTarget)>()
     {
-      if let Some(tile_entity) = working_tile
-        && let Some(prefab) = scene.get_parent_prefab_owned("Prefab::Wet Dirt")
-        && let Some(model) = prefab.get::<ModelComponent>()
-      {
-        scene.add_component(tile_entity, WateredTile {});
-        scene.add_component(tile_entity, model.clone());
+      if !input.check(InputState::Action) || !target.action.is("Collect") {
+        continue;
       }
-    }
 
-    for (_, (model, _, _)) in scene
-      .query_mut::<(&mut ModelComponent, &Tile, &CollisionEnter<Action, Tile>)>()
-      .without::<WateredTile>()
-    {
-      model.color = Vector3::new(1.0, 1.0, 0.0);
-      model.color_intensity = 0.1;
+      if let Some(drop) = drops.get(&target.entity) {
+        collections.push((player_entity, target.entity, drop.clone()));
+      } else if let Some(id) = spaces.get(&network.connection_id) {
+        insertions.push((target.entity, *id));
+      }
     }
 
-    for (_, (model, _, _)) in scene
-      .query_mut::<(&mut ModelComponent, &Tile, &Collision<Action, Tile>)>()
-      .without::<WateredTile>()
-    {
-      model.color_intensity = 0.0;
+    for (entity, parent_id) in insertions {
+      scene.add_component(entity, ParentComponent::new(*parent_id));
+      scene.add_component(entity, TransformComponent::default());
     }
-  }
 
-  pub fn handle_throw_seeds(&self, scene: &mut Scene, backpack: &mut Backpack) {
-    let delta_time = backpack.get::<Seconds>().unwrap();
-
-    for (_, (input, character, state, collision)) in scene
-      .query_mut::<(
-        &GameInput,
-        &mut Character,
-        &mut CharacterState,
-        &Collision<Action, Tile>,
-      )>()
-      .without::<CropTile>()
-    {
-      if input.check(InputState::Action)
-        && let ActionTypes::ThrowSeed = character.action
-        && character.has_at_least(&Item::Seed(CropType::Pumpkin), 1)
-        && let CharacterState::Normal | CharacterState::Running = state
+    for (player_entity, drop_entity, InventoryItem { item, quantity }) in collections {
+      let quantity = match quantity {
+        Quantity::Finite(quantity) => quantity,
+        Quantity::Empty | Quantity::Infinite => continue,
+      };
+
+      let remaining = match scene.get_components_mut::<&mut Character>(player_entity) {
+        Some(character) => match character.award(item, quantity) {
+          Ok(_) => 0,
+          Err(InventoryError::Full { remaining, .. }) => remaining,
+          Err(_) => quantity,
+        },
+        None => continue,
+      };
+
+      if remaining == 0 {
+        let _ = scene.despawn(drop_entity);
+      } else if let Some(pickup) = scene.get_components_mut::<&mut Pickup>(drop_entity)
+        && let Some(drop) = &mut pickup.item
       {
-        *state =
-          CharacterState::ThrowingSeed(collision.other, Level::to_max(1.0, Seconds::new(4.0)));
-        character.decrement_by(&Item::Seed(CropType::Pumpkin), 1);
+        drop.quantity = Quantity::Finite(remaining);
       }
     }
 
-    let mut working_tile = None;
-    for (_, (input, character)) in scene.query_mut::<(&GameInput, &mut CharacterState)>() {
-      if let CharacterState::ThrowingSeed(entity, timing) = character {
-        if let Some(_) = timing.tick() {
-          working_tile = Some(*entity);
-          *character = CharacterState::Normal;
-        }
+    // Fertilized tiles keep their tint, the farm actions highlight whichever
+    // tile is targeted on top of it
+    for (_, (model, tile)) in scene.query_mut::<(&mut ModelComponent, &Tile)>() {
+      if let Some(tint) = tile.get_tint() {
+        model.color = tint;
+        model.color_intensity = 0.2;
+      } else {
+        model.color_intensity = 0.0;
       }
     }
+  }
 
-    {
-      if let Some(tile_entity) = working_tile
-        && let Some(prefabs) = scene.get_prefab_owned(CropType::Pumpkin.get_prefab())
-        && let Some((mut parent, _)) = prefabs
-          .iter()
-          .cloned()
-          .find(|(prefab, _)| prefab.tag.name == CropType::Pumpkin.get_prefab())
-        && let Some((mut prefab, _)) = prefabs
-          .iter()
-          .cloned()
-          .find(|(prefab, _)| prefab.tag.name == "Seeds")
-        && let Some(transform) = scene
-          .get_components_mut::<&TransformComponent>(tile_entity)
-          .cloned()
+  /// Fertilizer only lasts for the season it was spread in
+  pub fn handle_fertilizer_expiry(&self, scene: &mut Scene, backpack: &mut Backpack) {
+    let season = match backpack.get::<DayCycle>() {
+      Some(DayCycle {
+        is_new_day: true,
+        calendar,
+        ..
+      }) => calendar.get_season_count(),
+      _ => return,
+    };
+
+    for (_, tile) in scene.query_mut::<&mut Tile>() {
+      if let Some(fertilized) = &tile.fertilized
+        && fertilized.season != season
       {
-        scene.add_component(tile_entity, CropTile {});
-        let crop_entity = scene.create_raw_entity("Pumpkin Crop");
-        prefab.transform = transform;
-        prefab.remove::<ParentComponent>();
-        scene.create_with_prefab(crop_entity, parent);
-        scene.create_with_prefab(crop_entity, prefab);
+        tile.fertilized = None;
       }
     }
-
-    for (_, (model, _, _)) in scene
-      .query_mut::<(&mut ModelComponent, &Tile, &CollisionEnter<Action, Tile>)>()
-      .without::<CropTile>()
-    {
-      model.color = Vector3::new(0.0, 1.0, 1.0);
-      model.color_intensity = 0.1;
-    }
-    for (_, (model, _, _)) in scene
-      .query_mut::<(&mut ModelComponent, &Tile, &CollisionExit<Action, Tile>)>()
-      .without::<CropTile>()
-    {
-      model.color_intensity = 0.0;
-    }
   }
 
-  pub fn handle_harvest(&self, scene: &mut Scene, backpack: &mut Backpack) {
-    let delta_time = backpack.get::<Seconds>().unwrap();
-
+  /// Puts away the crop a player is showing off as soon as they do anything
+  /// else
+  pub fn handle_showoff(&self, scene: &mut Scene) {
     let mut cleanup_showoff = false;
     for (_, (input, character)) in scene.query_mut::<(&GameInput, &mut CharacterState)>() {
       if (input.state.contains(InputState::Action)
@@ -298,179 +371,125 @@ impl PickupsSystem {
         let _ = scene.despawn(entity);
       }
     }
+  }
 
-    for (_, (input, character, state, collision)) in scene.query_mut::<(
+  pub fn handle_sales(&self, scene: &mut Scene) {
+    let mut deposits = vec![];
+    for (_, (id, input, character, state, target)) in scene.query_mut::<(
+      &IdComponent,
       &GameInput,
       &mut Character,
-      &mut CharacterState,
-      &Collision<Action, Harvestable>,
+      &CharacterState,
+      &InteractionTarget,
     )>() {
       if input.check(InputState::Action)
-        && let ActionTypes::Harvest = character.action
+        && target.action.is("Sell")
         && let CharacterState::Normal | CharacterState::Running = state
       {
-        *state = CharacterState::Harvesting(collision.other, Level::to_max(1.0, Seconds::new(4.0)));
+        let items = character.take_sellables();
+        if !items.is_empty() {
+          deposits.push((target.entity, **id, items));
+        }
       }
     }
 
-    let mut harvesting_entities = vec![];
-    for (player_entity, (transform, input, character)) in
-      scene.query_mut::<(&TransformComponent, &GameInput, &mut CharacterState)>()
-    {
-      if let CharacterState::Harvesting(harvesting_entity, timing) = character
-        && let Some(_) = timing.tick()
-      {
-        harvesting_entities.push((player_entity, *harvesting_entity, transform.clone()));
-        *character = CharacterState::Normal;
+    for (bin_entity, seller, items) in deposits {
+      if let Some(bin) = scene.get_components_mut::<&mut SalesBin>(bin_entity) {
+        for InventoryItem { item, quantity } in items {
+          if let Quantity::Finite(quantity) = quantity {
+            bin.deposit(seller, item, quantity);
+          }
+        }
       }
     }
+  }
 
-    for (player_entity, harvesting_entity, player_transform) in harvesting_entities {
-      let mut is_showoff = None;
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn handle_payout(&self, scene: &mut Scene, backpack: &mut Backpack) {
+    match backpack.get::<DayCycle>() {
+      Some(DayCycle {
+        is_new_day: true, ..
+      }) => {}
+      _ => return,
+    }
 
-      let crop = match scene.get_components_mut::<&Crop>(harvesting_entity) {
-        Some(data) => data.clone(),
-        None => continue,
-      };
+    let mut market = backpack.get_mut::<Market>();
 
-      if let Some((character, state)) =
-        scene.get_components_mut::<(&mut Character, &mut CharacterState)>(player_entity)
-      {
-        is_showoff = character.award(Item::Crop(crop.crop), crop.award);
+    let mut reports: HashMap<PrefabId, SalesReport> = HashMap::new();
+    for (_, bin) in scene.query_mut::<&mut SalesBin>() {
+      for sale in bin.contents.drain(..) {
+        let price = match &market {
+          Some(market) => market.get_price(&sale.item),
+          None => sale.item.get_price(),
+        };
+        let price = match price {
+          Some(price) => price,
+          None => continue,
+        };
 
-        // NOTE: if there is no room in the inventory, `is_showoff == None` and
-        // we still need to handle that case
-        if let Some(true) = is_showoff {
-          *state = CharacterState::ShowingOff {
-            item: Item::Crop(crop.crop),
-          };
+        // Crops sold today are paid at today's price, but flood the market for
+        // the ones sold tomorrow
+        if let Some(market) = &mut market {
+          market.record_sale(&sale.item, sale.quantity);
         }
-      }
-
-      let _ = scene.despawn(harvesting_entity);
-
-      if let Some(true) = is_showoff
-        && let Some(prefabs) = scene.get_prefab_owned(crop.crop.get_prefab())
-        && let Some((mut parent, _)) = prefabs
-          .iter()
-          .cloned()
-          .find(|(prefab, _)| prefab.tag.name == crop.crop.get_prefab())
-        && let Some((mut prefab, _)) = prefabs
-          .iter()
-          .cloned()
-          .find(|(prefab, _)| prefab.tag.name == Stage::Display.get_prefab())
-      {
-        let crop_entity = scene.create_raw_entity("Display");
 
-        prefab.transform = player_transform;
-        prefab.transform.translation -= Vector3::z();
-        prefab.transform.translation -= Vector3::y() * 0.25;
-
-        prefab.remove::<ParentComponent>();
-        if let Some(crop) = parent.get_mut::<Crop>() {
-          crop.stage = Stage::Display;
-        }
-        scene.create_with_prefab(crop_entity, parent);
-        scene.create_with_prefab(crop_entity, prefab);
-        scene.add_local_component(crop_entity, Showoff);
+        let report = reports.entry(sale.seller).or_insert_with(|| SalesReport {
+          seller: sale.seller,
+          items: vec![],
+          cash: 0,
+        });
+        report.cash += price * sale.quantity as u64;
+        report.items.push(InventoryItem {
+          item: sale.item,
+          quantity: Quantity::Finite(sale.quantity),
+        });
       }
     }
 
-    for (_, (model, _, _)) in scene.query_mut::<(
-      &mut ModelComponent,
-      &Harvestable,
-      &CollisionEnter<Action, Harvestable>,
-    )>() {
-      model.color = Vector3::new(1.0, 0.0, 1.0);
-      model.color_intensity = 0.1;
-    }
-    for (_, (model, _, _)) in scene.query_mut::<(
-      &mut ModelComponent,
-      &Harvestable,
-      &CollisionExit<Action, Harvestable>,
-    )>() {
-      model.color_intensity = 0.0;
+    for (_, (id, character)) in scene.query_mut::<(&IdComponent, &mut Character)>() {
+      if let Some(report) = reports.get(&**id) {
+        character.cash += report.cash;
+        self.multiplayer.broadcast_custom(report.clone());
+      }
     }
   }
 
-  pub fn handle_sales(&self, scene: &mut Scene, backpack: &mut Backpack) {
-    let delta_time = backpack.get::<Seconds>().unwrap();
-
-    /*
-      let mut harvesting_entities = vec![];
-      for (player_entity, (transform, input, character)) in
-        scene.query_mut::<(&TransformComponent, &GameInput, &mut CharacterState)>()
-      {
-        if let CharacterState::Harvesting(harvesting_entity, timing) = character
-          && let Some(_) = timing.tick()
-        {
-          harvesting_entities.push((player_entity, *harvesting_entity, transform.clone()));
-          *character = CharacterState::Normal;
-        }
+  #[cfg(target_arch = "wasm32")]
+  pub fn handle_receive_sales(&self, scene: &mut Scene) {
+    let player = match scene.query_one::<(&IdComponent, &SelfComponent)>() {
+      Some((_, (id, _))) => **id,
+      None => return,
+    };
+
+    while let Ok(report) = self.multiplayer.try_recv_custom::<SalesReport>() {
+      if report.seller == player {
+        self.browser.send(Message::CompleteSale {
+          items: report.items,
+          cash: report.cash,
+        });
       }
+    }
+  }
 
-      for (player_entity, harvesting_entity, player_transform) in harvesting_entities {
-        let mut is_showoff = false;
-
-        let crop = match scene.get_components_mut::<&Crop>(harvesting_entity) {
-          Some(data) => data.clone(),
-          None => continue,
-        };
-
-        if let Some((inventory, character)) =
-          scene.get_components_mut::<(&mut GameInventory, &mut CharacterState)>(player_entity)
-        {
-          is_showoff = inventory.award(Item::Crop(crop.crop), crop.award);
-          if is_showoff {
-            *character = CharacterState::ShowingOff {
-              item: Item::Crop(crop.crop),
-            };
-          }
-        }
+  /// Dries every watered tile at the start of a new day, so they need watering
+  /// again for their crops to keep growing
+  pub fn handle_dry_tiles(&self, scene: &mut Scene, backpack: &mut Backpack) {
+    match backpack.get::<DayCycle>() {
+      Some(DayCycle {
+        is_new_day: true, ..
+      }) => {}
+      _ => return,
+    }
 
-        let _ = scene.despawn(harvesting_entity);
-
-        if is_showoff
-          && let Some(prefabs) = scene.get_prefab_owned(crop.crop.get_prefab())
-          && let Some((mut parent, _)) = prefabs
-            .iter()
-            .cloned()
-            .find(|(prefab, _)| prefab.tag.name == crop.crop.get_prefab())
-          && let Some((mut prefab, _)) = prefabs
-            .iter()
-            .cloned()
-            .find(|(prefab, _)| prefab.tag.name == Stage::Display.get_prefab())
-        {
-          let crop_entity = scene.create_raw_entity("Display");
-
-          prefab.transform = player_transform;
-          prefab.transform.translation -= Vector3::z();
-          prefab.transform.translation -= Vector3::y() * 0.25;
-
-          prefab.remove::<ParentComponent>();
-          if let Some(crop) = parent.get_mut::<Crop>() {
-            crop.stage = Stage::Display;
-          }
-          scene.create_with_prefab(crop_entity, parent);
-          scene.create_with_prefab(crop_entity, prefab);
-        }
+    let mut tiles = vec![];
+    for (entity, tile) in scene.query_mut::<&Tile>() {
+      if tile.soil.is_watered() {
+        tiles.push(entity);
       }
-    */
-
-    for (_, (model, _, _)) in scene.query_mut::<(
-      &mut ModelComponent,
-      &SalesBin,
-      &CollisionEnter<Action, SalesBin>,
-    )>() {
-      model.color = Vector3::new(0.0, 0.0, 1.0);
-      model.color_intensity = 0.1;
     }
-    for (_, (model, _, _)) in scene.query_mut::<(
-      &mut ModelComponent,
-      &SalesBin,
-      &CollisionExit<Action, SalesBin>,
-    )>() {
-      model.color_intensity = 0.0;
+
+    for entity in tiles {
+      change_soil(scene, entity, Soil::dried);
     }
   }
 
@@ -487,62 +506,98 @@ impl PickupsSystem {
 
   pub fn handle_plant_growth(&self, scene: &mut Scene, backpack: &mut Backpack) {
     let delta_time = backpack.get::<Seconds>().unwrap().clone();
-    //panic!("Continue here");
+    let (is_new_day, season) = match backpack.get::<DayCycle>() {
+      Some(cycle) => (cycle.is_new_day, Some(cycle.calendar.season)),
+      None => (false, None),
+    };
+
+    let mut tiles = HashMap::new();
+    for (_, (id, tile)) in scene.query_mut::<(&IdComponent, &Tile)>() {
+      tiles.insert(**id, tile.clone());
+    }
+
     let mut growing_crops = vec![];
-    for (entity, (transform, crop)) in scene.query_mut::<(&TransformComponent, &mut Crop)>() {
-      crop.phase_timing += delta_time;
-      match crop.stage {
-        Stage::Seeds => {
-          if crop.phase_timing > crop.seed_timeout {
-            growing_crops.push((entity.clone(), transform.clone(), crop.crop, crop.stage));
-            crop.phase_timing = Seconds::new(0.0);
+    for (entity, crop) in scene.query_mut::<&mut Crop>() {
+      let entry = match crop.crop.get_entry() {
+        Some(entry) => entry,
+        None => continue,
+      };
+
+      let tile = crop.tile.and_then(|tile| tiles.get(&tile));
+      let is_watered = match tile {
+        Some(tile) => tile.soil.is_watered(),
+        None => false,
+      };
+      let fertilizer = tile.and_then(|tile| tile.get_fertilizer());
+
+      // Tiles dry out as the day starts, so a dry tile at this point means it
+      // was left unwatered for the whole day before
+      if is_new_day && crop.stage.is_growing() {
+        match is_watered {
+          true => crop.dry_days = 0,
+          false => {
+            crop.dried_out = true;
+            crop.dry_days += 1;
           }
         }
-        Stage::Seedling => {
-          if crop.phase_timing > crop.seedling_timeout {
-            growing_crops.push((entity.clone(), transform.clone(), crop.crop, crop.stage));
-            crop.phase_timing = Seconds::new(0.0);
-          }
+      }
+
+      // Crops die when their season is over, or after too many days dry
+      if is_new_day && (crop.stage.is_growing() || crop.stage == Stage::Mature) {
+        let is_withering = crop.dry_days >= entry.wither_days;
+        if is_withering || !is_in_season(&crop.crop, season) {
+          crop.phase_timing = Seconds::new(0.0);
+          growing_crops.push((entity, Stage::Withered));
+          continue;
         }
-        Stage::Flowering => {
-          if crop.phase_timing > crop.flowering_timeout {
-            growing_crops.push((entity.clone(), transform.clone(), crop.crop, crop.stage));
+      }
+
+      match crop.stage {
+        Stage::Seeds | Stage::Seedling | Stage::Flowering => {
+          if !is_watered {
+            continue;
+          }
+
+          let timeout = match (entry.get_timeout(&crop.stage), fertilizer) {
+            (Some(timeout), Some(fertilizer)) => Some(fertilizer.scale_timeout(timeout)),
+            (timeout, _) => timeout,
+          };
+
+          crop.phase_timing += delta_time;
+          if let Some(timeout) = timeout
+            && crop.phase_timing > timeout
+          {
+            // The boost is rolled once, as the crop is about to mature
+            if crop.stage == Stage::Flowering
+              && let Some(fertilizer) = fertilizer
+              && fertilizer.roll_quality()
+            {
+              crop.boost = 1;
+            }
+            crop.stages_grown += 1;
+            if !crop.dried_out {
+              crop.stages_watered += 1;
+            }
+            crop.dried_out = false;
             crop.phase_timing = Seconds::new(0.0);
+            let next_stage = crop.stage.get_next_stage();
+            growing_crops.push((entity, next_stage));
           }
         }
         Stage::Mature => {
-          // NOTE: Implement rotten crop
-          /*
-          if crop.phase_timing > crop.mature_timeout {
-            growing_crops.push((entity.clone(), transform.clone(), crop.crop, crop.stage));
+          // Mature crops rot when they are not harvested in time, watered or not
+          crop.phase_timing += delta_time;
+          if crop.phase_timing > entry.mature_timeout {
+            crop.phase_timing = Seconds::new(0.0);
+            growing_crops.push((entity, Stage::Rotten));
           }
-          */
         }
-        Stage::Display => {}
+        Stage::Display | Stage::Rotten | Stage::Withered => {}
       };
     }
 
-    for (entity, transform, crop, stage) in growing_crops {
-      if let Some(prefabs) = scene.get_prefab_owned(crop.get_prefab())
-        && let Some((mut parent, _)) = prefabs
-          .iter()
-          .cloned()
-          .find(|(prefab, _)| prefab.tag.name == crop.get_prefab())
-        && let Some((mut prefab, _)) = prefabs
-          .iter()
-          .cloned()
-          .find(|(prefab, _)| prefab.tag.name == stage.get_next_stage().get_prefab())
-      {
-        let _ = scene.despawn(entity);
-        let crop_entity = scene.create_raw_entity("Crop");
-        prefab.transform = transform;
-        prefab.remove::<ParentComponent>();
-        if let Some(crop) = parent.get_mut::<Crop>() {
-          crop.stage = stage.get_next_stage();
-        }
-        scene.create_with_prefab(crop_entity, parent);
-        scene.create_with_prefab(crop_entity, prefab);
-      }
+    for (entity, next_stage) in growing_crops {
+      change_stage(scene, entity, next_stage);
     }
   }
 }
@@ -553,14 +608,17 @@ impl System for PickupsSystem {
   }
 
   fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
-    self.handle_select_action(scene);
     self.handle_add_state(scene);
     self.handle_pickup(scene);
-    self.handle_water_sources(scene, backpack);
-    self.handle_watering_tiles(scene, backpack);
-    self.handle_throw_seeds(scene, backpack);
-    self.handle_harvest(scene, backpack);
+    self.handle_showoff(scene);
+    self.handle_sales(scene);
+    #[cfg(not(target_arch = "wasm32"))]
+    self.handle_payout(scene, backpack);
+    #[cfg(target_arch = "wasm32")]
+    self.handle_receive_sales(scene);
     self.handle_plant_growth(scene, backpack);
+    self.handle_dry_tiles(scene, backpack);
+    self.handle_fertilizer_expiry(scene, backpack);
     //self.handle_update_ui(scene, backpack);
   }
 }
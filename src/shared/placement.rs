@@ -0,0 +1,262 @@
+use crate::shared::components::{Character, Chest, FarmWork, Placeable, Soil, Tile};
+use crate::shared::farming::FarmAction;
+use engine::{
+  application::scene::{Scene, TransformComponent},
+  nalgebra::Vector3,
+  systems::{Backpack, Initializable, Inventory, System},
+  utils::units::Seconds,
+  Entity,
+};
+
+#[cfg(target_arch = "wasm32")]
+use crate::shared::components::InteractionTarget;
+#[cfg(target_arch = "wasm32")]
+use engine::application::components::{ModelComponent, SelfComponent};
+
+pub fn is_same_tile(a: &Vector3<f32>, b: &Vector3<f32>) -> bool {
+  (a.x - b.x).abs() < 0.5 && (a.z - b.z).abs() < 0.5
+}
+
+/// Whether something is growing on the tile or an object was placed on it
+pub fn is_blocked(scene: &mut Scene, tile_entity: Entity) -> bool {
+  let position = match scene.get_components_mut::<(&TransformComponent, &Tile)>(tile_entity) {
+    Some((_, tile)) if matches!(tile.soil, Soil::Planted { .. }) => return true,
+    Some((transform, _)) => transform.translation,
+    None => return true,
+  };
+
+  for (_, (transform, _)) in scene.query_mut::<(&TransformComponent, &Placeable)>() {
+    if is_same_tile(&transform.translation, &position) {
+      return true;
+    }
+  }
+  false
+}
+
+/// Puts the object the player is holding down on the tile in front of them, as
+/// long as nothing is growing or standing on it already
+#[derive(Default)]
+pub struct PlaceItem {}
+
+impl FarmAction for PlaceItem {
+  fn name(&self) -> &'static str {
+    "Place"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Place"
+  }
+
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Placing..")
+  }
+
+  // Blocked tiles are still targeted so the preview can show they are taken
+  fn get_candidates(&self, scene: &mut Scene, player: Entity) -> Vec<Entity> {
+    match scene.get_components_mut::<&Character>(player) {
+      Some(character) if character.get_selected_placeable().is_some() => {}
+      _ => return vec![],
+    }
+
+    let mut tiles = vec![];
+    for (entity, _) in scene.query_mut::<&Tile>() {
+      tiles.push(entity);
+    }
+    tiles
+  }
+
+  fn start(
+    &self,
+    scene: &mut Scene,
+    _: &mut Backpack,
+    player: Entity,
+    target: Entity,
+  ) -> Option<FarmWork> {
+    if is_blocked(scene, target) {
+      return None;
+    }
+
+    let character = scene.get_components_mut::<&mut Character>(player)?;
+    let (item, _) = character.get_selected_placeable()?;
+    character.remove(&item, 1).ok()?;
+
+    Some(FarmWork::new(self.name(), target, Seconds::new(0.5)).with_item(item))
+  }
+
+  // Only the server spawns placed objects, clients see them once it does
+  #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+  fn complete(&self, scene: &mut Scene, _: &mut Backpack, player: Entity, work: FarmWork) {
+    let (tile_entity, item) = match (work.get_target(), work.item) {
+      (Some(tile_entity), Some(item)) => (tile_entity, item),
+      _ => return,
+    };
+
+    // Someone else may have put something down on the tile in the meantime, in
+    // which case the player gets their item back
+    if is_blocked(scene, tile_entity) {
+      if let Some(character) = scene.get_components_mut::<&mut Character>(player)
+        && let Err(error) = character.award(item, 1)
+      {
+        log::warn!("could not return the item that was not placed: {:}", error);
+      }
+      return;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(prefab_name) = item.get_placed_prefab()
+      && let Some(position) = scene
+        .get_components_mut::<&TransformComponent>(tile_entity)
+        .map(|transform| transform.translation)
+    {
+      scene.spawn_prefab_with(prefab_name, |prefab| {
+        prefab.transform.translation = position;
+      });
+    }
+  }
+}
+
+/// Takes a placed object back into the inventory of the player. Chests have to
+/// be emptied first so nothing stored in them is lost.
+#[derive(Default)]
+pub struct PickUp {}
+
+impl FarmAction for PickUp {
+  fn name(&self) -> &'static str {
+    "PickUp"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Pick Up"
+  }
+
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Picking up..")
+  }
+
+  fn get_candidates(&self, scene: &mut Scene, _: Entity) -> Vec<Entity> {
+    let mut placed = vec![];
+    for (entity, (_, chest)) in scene.query_mut::<(&Placeable, Option<&Chest>)>() {
+      if chest.is_none_or(|chest| chest.is_empty()) {
+        placed.push(entity);
+      }
+    }
+    placed
+  }
+
+  fn start(&self, _: &mut Scene, _: &mut Backpack, _: Entity, target: Entity) -> Option<FarmWork> {
+    Some(FarmWork::new(self.name(), target, Seconds::new(0.5)))
+  }
+
+  #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+  fn complete(&self, scene: &mut Scene, _: &mut Backpack, player: Entity, work: FarmWork) {
+    let placed_entity = match work.get_target() {
+      Some(placed_entity) => placed_entity,
+      None => return,
+    };
+    // Something may have been stored in a chest while it was being picked up
+    let item = match scene.get_components_mut::<(&Placeable, Option<&Chest>)>(placed_entity) {
+      Some((placeable, chest)) if chest.is_none_or(|chest| chest.is_empty()) => {
+        placeable.item.clone()
+      }
+      _ => return,
+    };
+
+    let is_awarded = match scene.get_components_mut::<&mut Character>(player) {
+      Some(character) => character.award(item, 1).is_ok(),
+      None => false,
+    };
+
+    // Only the server despawns placed objects, clients see them go away once it
+    // does
+    #[cfg(not(target_arch = "wasm32"))]
+    if is_awarded {
+      let _ = scene.despawn(placed_entity);
+    }
+  }
+}
+
+pub struct PlacementSystem {
+  // Preview of the object being placed along with the prefab it shows
+  #[cfg(target_arch = "wasm32")]
+  ghost: Option<(Entity, &'static str)>,
+}
+
+impl Initializable for PlacementSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {
+      #[cfg(target_arch = "wasm32")]
+      ghost: None,
+    }
+  }
+}
+
+impl PlacementSystem {
+  /// Shows the object the local player is about to place snapped to the tile
+  /// they target, tinted red when it can not go there
+  #[cfg(target_arch = "wasm32")]
+  pub fn handle_preview(&mut self, scene: &mut Scene) {
+    let preview = match scene.query_one::<(&InteractionTarget, &Character, &SelfComponent)>() {
+      Some((_, (target, character, _))) if target.action.is("Place") => character
+        .get_selected_placeable()
+        .map(|(_, prefab_name)| (target.entity, prefab_name)),
+      _ => None,
+    };
+
+    let (tile_entity, prefab_name) = match preview {
+      Some(preview) => preview,
+      None => {
+        if let Some((ghost_entity, _)) = self.ghost.take() {
+          let _ = scene.despawn(ghost_entity);
+        }
+        return;
+      }
+    };
+
+    if let Some((ghost_entity, shown)) = self.ghost
+      && shown != prefab_name
+    {
+      let _ = scene.despawn(ghost_entity);
+      self.ghost = None;
+    }
+
+    if self.ghost.is_none()
+      && let Some(prefab) = scene.get_parent_prefab_owned(prefab_name)
+      && let Some(model) = prefab.get::<ModelComponent>()
+    {
+      let ghost_entity = scene.create_raw_entity("Placement Preview");
+      scene.add_local_component(ghost_entity, model.clone());
+      scene.add_local_component(ghost_entity, prefab.transform.clone());
+      self.ghost = Some((ghost_entity, prefab_name));
+    }
+
+    let is_blocked = is_blocked(scene, tile_entity);
+    let position = scene
+      .get_components_mut::<&TransformComponent>(tile_entity)
+      .map(|transform| transform.translation);
+
+    if let Some((ghost_entity, _)) = self.ghost
+      && let Some(position) = position
+      && let Some((transform, model)) =
+        scene.get_components_mut::<(&mut TransformComponent, &mut ModelComponent)>(ghost_entity)
+    {
+      transform.translation = position;
+      model.color = match is_blocked {
+        true => Vector3::new(1.0, 0.0, 0.0),
+        false => Vector3::new(0.0, 1.0, 0.0),
+      };
+      model.color_intensity = 0.5;
+    }
+  }
+}
+
+impl System for PlacementSystem {
+  fn get_name(&self) -> &'static str {
+    "PlacementSystem"
+  }
+
+  #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+  fn run(&mut self, scene: &mut Scene, _: &mut Backpack) {
+    #[cfg(target_arch = "wasm32")]
+    self.handle_preview(scene);
+  }
+}
@@ -0,0 +1,295 @@
+use crate::shared::components::{Character, FarmWork, InventoryError, Item, Vendor};
+use crate::shared::farming::{is_in_reach, FarmAction};
+use engine::{
+  application::scene::{IdComponent, PrefabId, Scene},
+  nalgebra::Vector3,
+  systems::{Backpack, Initializable, Inventory, System},
+  tsify, Entity,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::shared::game_input::GameInput;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::shared::timeofday::DayCycle;
+
+#[cfg(target_arch = "wasm32")]
+use crate::client::browser::Message;
+#[cfg(target_arch = "wasm32")]
+use crate::shared::components::VendorStock;
+#[cfg(target_arch = "wasm32")]
+use crate::shared::farming::{get_local_player, get_used_target};
+#[cfg(target_arch = "wasm32")]
+use engine::systems::browser::BrowserController;
+
+/// A purchase requested from the shop window, validated by the server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, tsify::Tsify)]
+pub struct Purchase {
+  pub vendor: PrefabId,
+  pub item: Item,
+  pub quantity: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShopError {
+  NotForSale,
+  OutOfStock { requested: usize, available: usize },
+  NotEnoughCash { requested: u64, available: u64 },
+  Inventory(InventoryError),
+}
+
+impl From<InventoryError> for ShopError {
+  fn from(error: InventoryError) -> Self {
+    Self::Inventory(error)
+  }
+}
+
+impl std::fmt::Display for ShopError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::NotForSale => write!(f, "The vendor does not sell that"),
+      Self::OutOfStock {
+        requested,
+        available,
+      } => write!(
+        f,
+        "Out of stock, wanted {:} but only {:} available",
+        requested, available
+      ),
+      Self::NotEnoughCash {
+        requested,
+        available,
+      } => write!(
+        f,
+        "Not enough cash, wanted {:} but only {:} available",
+        requested, available
+      ),
+      Self::Inventory(error) => write!(f, "{:}", error),
+    }
+  }
+}
+
+impl std::error::Error for ShopError {}
+
+impl Vendor {
+  /// Sells `quantity` of `item` to `character`, who only pays when everything
+  /// fits in their inventory.
+  pub fn sell(
+    &mut self,
+    character: &mut Character,
+    item: &Item,
+    quantity: usize,
+  ) -> Result<(), ShopError> {
+    let stock = self.get_stock_mut(item).ok_or(ShopError::NotForSale)?;
+    if stock.available < quantity {
+      return Err(ShopError::OutOfStock {
+        requested: quantity,
+        available: stock.available,
+      });
+    }
+
+    let cost = stock.price * quantity as u64;
+    if character.cash < cost {
+      return Err(ShopError::NotEnoughCash {
+        requested: cost,
+        available: character.cash,
+      });
+    }
+
+    let mut next = character.clone();
+    next.award(item.clone(), quantity)?;
+    next.cash -= cost;
+
+    *character = next;
+    stock.available -= quantity;
+    Ok(())
+  }
+}
+
+/// Opens the shop of a vendor
+#[derive(Default)]
+pub struct Shop {}
+
+impl FarmAction for Shop {
+  fn name(&self) -> &'static str {
+    "Shop"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Shop"
+  }
+
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Shopping..")
+  }
+
+  fn is_selectable(&self) -> bool {
+    false
+  }
+
+  fn get_color(&self) -> Vector3<f32> {
+    Vector3::new(1.0, 0.5, 0.0)
+  }
+
+  fn get_candidates(&self, scene: &mut Scene, _: Entity) -> Vec<Entity> {
+    let mut vendors = vec![];
+    for (entity, _) in scene.query_mut::<&Vendor>() {
+      vendors.push(entity);
+    }
+    vendors
+  }
+
+  // There is no work to it, the shop system opens the window on the client
+  fn start(&self, _: &mut Scene, _: &mut Backpack, _: Entity, _: Entity) -> Option<FarmWork> {
+    None
+  }
+
+  fn complete(&self, _: &mut Scene, _: &mut Backpack, _: Entity, _: FarmWork) {}
+}
+
+pub struct ShopSystem {
+  #[cfg(target_arch = "wasm32")]
+  browser: BrowserController<Message>,
+  #[cfg(target_arch = "wasm32")]
+  open: Option<(Entity, Vec<VendorStock>)>,
+}
+
+impl Initializable for ShopSystem {
+  #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+  fn initialize(inventory: &Inventory) -> Self {
+    #[cfg(target_arch = "wasm32")]
+    let browser = inventory.get::<BrowserController<Message>>().clone();
+    Self {
+      #[cfg(target_arch = "wasm32")]
+      browser,
+      #[cfg(target_arch = "wasm32")]
+      open: None,
+    }
+  }
+}
+
+impl ShopSystem {
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn handle_restock(&self, scene: &mut Scene, backpack: &mut Backpack) {
+    match backpack.get::<DayCycle>() {
+      Some(DayCycle {
+        is_new_day: true, ..
+      }) => {}
+      _ => return,
+    }
+
+    for (_, vendor) in scene.query_mut::<&mut Vendor>() {
+      vendor.restock();
+    }
+  }
+
+  /// Sells to players standing at the vendor, ignoring purchases from vendors
+  /// they are not next to.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn handle_purchases(&self, scene: &mut Scene) {
+    let mut purchases = vec![];
+    for (entity, input) in scene.query_mut::<&GameInput>() {
+      for purchase in &input.purchases {
+        purchases.push((entity, purchase.clone()));
+      }
+    }
+
+    let mut vendors = vec![];
+    for (entity, (id, _)) in scene.query_mut::<(&IdComponent, &Vendor)>() {
+      vendors.push((**id, entity));
+    }
+
+    for (player_entity, purchase) in purchases {
+      let vendor_entity = match vendors.iter().find(|(id, _)| id == &purchase.vendor) {
+        Some((_, entity)) => *entity,
+        None => continue,
+      };
+
+      if !is_in_reach(scene, player_entity, vendor_entity) {
+        log::warn!("rejected purchase {:?}: not at the vendor", purchase);
+        continue;
+      }
+
+      let mut vendor = match scene.get_components_mut::<&Vendor>(vendor_entity) {
+        Some(vendor) => vendor.clone(),
+        None => continue,
+      };
+
+      let result = match scene.get_components_mut::<&mut Character>(player_entity) {
+        Some(character) => vendor.sell(character, &purchase.item, purchase.quantity),
+        None => continue,
+      };
+
+      match result {
+        Ok(()) => {
+          if let Some(data) = scene.get_components_mut::<&mut Vendor>(vendor_entity) {
+            *data = vendor;
+          }
+        }
+        Err(error) => log::warn!("rejected purchase {:?}: {:}", purchase, error),
+      }
+    }
+  }
+
+  /// Opens the shop window once the player uses the vendor they target, and
+  /// keeps its stock up to date until they walk away.
+  #[cfg(target_arch = "wasm32")]
+  pub fn handle_shop_window(&mut self, scene: &mut Scene) {
+    let mut nearby = get_used_target(scene, "Shop");
+    let player = get_local_player(scene);
+    if nearby.is_none()
+      && let Some((open_entity, _)) = &self.open
+      && let Some(player_entity) = player
+      && is_in_reach(scene, player_entity, *open_entity)
+    {
+      nearby = Some(*open_entity);
+    }
+
+    let vendor = match nearby {
+      Some(entity) => match scene.get_components_mut::<(&IdComponent, &Vendor)>(entity) {
+        Some((id, vendor)) => Some((entity, **id, vendor.stock.clone())),
+        None => None,
+      },
+      None => None,
+    };
+
+    match vendor {
+      Some((entity, id, stock)) => {
+        let is_current = match &self.open {
+          Some((open_entity, open_stock)) => *open_entity == entity && *open_stock == stock,
+          None => false,
+        };
+
+        if !is_current {
+          self.browser.send(Message::OpenShop {
+            vendor: id,
+            stock: stock.clone(),
+          });
+          self.open = Some((entity, stock));
+        }
+      }
+      None => {
+        if self.open.take().is_some() {
+          self.browser.send(Message::CloseShop);
+        }
+      }
+    }
+  }
+}
+
+impl System for ShopSystem {
+  fn get_name(&self) -> &'static str {
+    "ShopSystem"
+  }
+
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+      self.handle_restock(scene, backpack);
+      self.handle_purchases(scene);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    self.handle_shop_window(scene);
+  }
+}
@@ -0,0 +1,62 @@
+use crate::shared::components::{Soil, Sprinkler, Tile};
+use crate::shared::pickups::change_soil;
+use crate::shared::timeofday::DayCycle;
+use engine::{
+  application::scene::{Scene, TransformComponent},
+  systems::{Backpack, Initializable, Inventory, System},
+};
+
+pub struct SprinklerSystem {}
+
+impl Initializable for SprinklerSystem {
+  fn initialize(_: &Inventory) -> Self {
+    Self {}
+  }
+}
+
+impl SprinklerSystem {
+  /// Waters every tile in range of a sprinkler as the day starts, right after
+  /// the tiles dried out
+  pub fn handle_watering(&self, scene: &mut Scene, backpack: &mut Backpack) {
+    match backpack.get::<DayCycle>() {
+      Some(DayCycle {
+        is_new_day: true, ..
+      }) => {}
+      _ => return,
+    }
+
+    let mut sprinklers = vec![];
+    for (_, (transform, sprinkler)) in scene.query_mut::<(&TransformComponent, &Sprinkler)>() {
+      sprinklers.push((transform.translation, sprinkler.clone()));
+    }
+
+    if sprinklers.is_empty() {
+      return;
+    }
+
+    let mut watered = vec![];
+    for (entity, (transform, _)) in scene.query_mut::<(&TransformComponent, &Tile)>() {
+      let is_covered = sprinklers.iter().any(|(position, sprinkler)| {
+        let offset = transform.translation - position;
+        sprinkler.covers(offset.x.round() as i32, offset.z.round() as i32)
+      });
+      if is_covered {
+        watered.push(entity);
+      }
+    }
+
+    for entity in watered {
+      change_soil(scene, entity, Soil::watered);
+    }
+  }
+}
+
+impl System for SprinklerSystem {
+  fn get_name(&self) -> &'static str {
+    "SprinklerSystem"
+  }
+
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    self.handle_watering(scene, backpack);
+  }
+}
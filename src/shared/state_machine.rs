@@ -1,5 +1,5 @@
 //use crate::shared::audio_components::{AudioGameStart, SoundtrackIntro};
-use crate::shared::components::{ActionTypes, ActiveCamera, Character, CharacterState, Player};
+use crate::shared::components::{ActiveCamera, Character, CharacterState, Player};
 use crate::shared::game_input::{GameInput, InputState};
 use chrono::{DateTime, TimeDelta, Utc};
 use engine::{
@@ -32,10 +32,28 @@ use tagged::registry::Prev;
 #[cfg(target_arch = "wasm32")]
 use crate::client::browser::Message;
 #[cfg(target_arch = "wasm32")]
+use crate::shared::farming::get_action;
+#[cfg(target_arch = "wasm32")]
 use engine::systems::browser::BrowserController;
 #[cfg(target_arch = "wasm32")]
 use engine::systems::rendering::CameraConfig;
 
+/// What the player is doing, or the action they would do next
+#[cfg(target_arch = "wasm32")]
+fn get_state_label(character: &Character, state: &CharacterState) -> String {
+  match state {
+    CharacterState::ShowingOff { item } => format!("Harvested {:}!", item),
+    CharacterState::Farming(work) => match get_action(&work.action) {
+      Some(action) => action.get_progress_label(work),
+      None => String::from("Working.."),
+    },
+    CharacterState::Normal | CharacterState::Running => match get_action(&character.action) {
+      Some(action) => String::from(action.get_label()),
+      None => character.action.to_string(),
+    },
+  }
+}
+
 pub struct StateMachineSystem {
   current_time: Seconds,
   physics: PhysicsController,
@@ -357,15 +375,7 @@ impl StateMachineSystem {
       if character != prev_character || state != prev_state {
         self.browser.send(Message::UpdateCharacter {
           character: character.clone(),
-          state: match (state, &character.action) {
-            (CharacterState::CollectingWater, _) => String::from("Collecting Water.."),
-            (CharacterState::WorkingTile(_), _) => String::from("Watering Soil.."),
-            (CharacterState::ThrowingSeed(_, _), _) => String::from("Planting Seed.."),
-            (CharacterState::Harvesting(_, _), _) => String::from("Harvesting..."),
-            (_, ActionTypes::WaterTile) => String::from("Water Soil"),
-            (_, ActionTypes::ThrowSeed) => String::from("Plant Seed"),
-            (_, ActionTypes::Harvest) => String::from("Harvest"),
-          },
+          state: get_state_label(character, state),
         });
       }
     }
@@ -413,15 +423,7 @@ impl StateMachineSystem {
       {
         self.browser.send(Message::UpdateCharacter {
           character: character.clone(),
-          state: match (state, &character.action) {
-            (CharacterState::CollectingWater, _) => String::from("Collecting Water.."),
-            (CharacterState::WorkingTile(_), _) => String::from("Watering Soil.."),
-            (CharacterState::ThrowingSeed(_, _), _) => String::from("Planting Seed.."),
-            (CharacterState::Harvesting(_, _), _) => String::from("Harvesting..."),
-            (_, ActionTypes::WaterTile) => String::from("Water Soil"),
-            (_, ActionTypes::ThrowSeed) => String::from("Plant Seed"),
-            (_, ActionTypes::Harvest) => String::from("Harvest"),
-          },
+          state: get_state_label(character, state),
         });
       }
     }
@@ -0,0 +1,267 @@
+use crate::shared::components::{Character, Chest, FarmWork, InventoryError};
+use crate::shared::farming::{is_in_reach, FarmAction};
+use engine::{
+  application::scene::{IdComponent, PrefabId, Scene},
+  nalgebra::Vector3,
+  systems::{Backpack, Initializable, Inventory, System},
+  tsify, Entity,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::shared::game_input::GameInput;
+
+#[cfg(target_arch = "wasm32")]
+use crate::client::browser::Message;
+#[cfg(target_arch = "wasm32")]
+use crate::shared::components::InventoryItem;
+#[cfg(target_arch = "wasm32")]
+use crate::shared::farming::{get_local_player, get_used_target};
+#[cfg(target_arch = "wasm32")]
+use engine::systems::browser::BrowserController;
+
+/// Moves items between a player and a chest. Requests are applied by the
+/// server one at a time, so two players reaching for the same stack can never
+/// both take it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, tsify::Tsify)]
+pub enum ChestCommand {
+  Deposit {
+    chest: PrefabId,
+    slot: usize,
+    quantity: usize,
+  },
+  Withdraw {
+    chest: PrefabId,
+    slot: usize,
+    quantity: usize,
+  },
+}
+
+impl ChestCommand {
+  pub fn get_chest(&self) -> PrefabId {
+    match self {
+      Self::Deposit { chest, .. } | Self::Withdraw { chest, .. } => *chest,
+    }
+  }
+}
+
+impl Chest {
+  /// Moves `quantity` out of the character's `slot` into the chest, or nothing
+  /// at all when it does not fit.
+  pub fn deposit(
+    &mut self,
+    character: &mut Character,
+    slot: usize,
+    quantity: usize,
+  ) -> Result<(), InventoryError> {
+    let mut next_character = character.clone();
+    let mut next_chest = self.clone();
+
+    let taken = next_character.take_slot(slot, quantity)?;
+    next_chest.store(taken.item, quantity)?;
+
+    *character = next_character;
+    *self = next_chest;
+    Ok(())
+  }
+
+  /// Moves `quantity` out of the chest's `slot` into the character inventory,
+  /// or nothing at all when it does not fit.
+  pub fn withdraw(
+    &mut self,
+    character: &mut Character,
+    slot: usize,
+    quantity: usize,
+  ) -> Result<(), InventoryError> {
+    let mut next_character = character.clone();
+    let mut next_chest = self.clone();
+
+    let taken = next_chest.take_slot(slot, quantity)?;
+    next_character.award(taken.item, quantity)?;
+
+    *character = next_character;
+    *self = next_chest;
+    Ok(())
+  }
+
+  pub fn apply(
+    &mut self,
+    character: &mut Character,
+    command: &ChestCommand,
+  ) -> Result<(), InventoryError> {
+    match *command {
+      ChestCommand::Deposit { slot, quantity, .. } => self.deposit(character, slot, quantity),
+      ChestCommand::Withdraw { slot, quantity, .. } => self.withdraw(character, slot, quantity),
+    }
+  }
+}
+
+/// Opens a chest to move items in and out of it
+#[derive(Default)]
+pub struct OpenChest {}
+
+impl FarmAction for OpenChest {
+  fn name(&self) -> &'static str {
+    "OpenChest"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Open Chest"
+  }
+
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Opening Chest..")
+  }
+
+  fn is_selectable(&self) -> bool {
+    false
+  }
+
+  fn get_color(&self) -> Vector3<f32> {
+    Vector3::new(0.6, 0.4, 0.2)
+  }
+
+  fn get_candidates(&self, scene: &mut Scene, _: Entity) -> Vec<Entity> {
+    let mut chests = vec![];
+    for (entity, _) in scene.query_mut::<&Chest>() {
+      chests.push(entity);
+    }
+    chests
+  }
+
+  // There is no work to it, the storage system opens the window on the client
+  fn start(&self, _: &mut Scene, _: &mut Backpack, _: Entity, _: Entity) -> Option<FarmWork> {
+    None
+  }
+
+  fn complete(&self, _: &mut Scene, _: &mut Backpack, _: Entity, _: FarmWork) {}
+}
+
+pub struct StorageSystem {
+  #[cfg(target_arch = "wasm32")]
+  browser: BrowserController<Message>,
+  #[cfg(target_arch = "wasm32")]
+  open: Option<(Entity, Vec<InventoryItem>)>,
+}
+
+impl Initializable for StorageSystem {
+  #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+  fn initialize(inventory: &Inventory) -> Self {
+    #[cfg(target_arch = "wasm32")]
+    let browser = inventory.get::<BrowserController<Message>>().clone();
+    Self {
+      #[cfg(target_arch = "wasm32")]
+      browser,
+      #[cfg(target_arch = "wasm32")]
+      open: None,
+    }
+  }
+}
+
+impl StorageSystem {
+  /// Applies the chest commands of players standing at that chest, ignoring
+  /// any for chests they are not next to.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn handle_commands(&self, scene: &mut Scene) {
+    let mut commands = vec![];
+    for (entity, input) in scene.query_mut::<&GameInput>() {
+      for command in &input.chests {
+        commands.push((entity, command.clone()));
+      }
+    }
+
+    let mut chests = vec![];
+    for (entity, (id, _)) in scene.query_mut::<(&IdComponent, &Chest)>() {
+      chests.push((**id, entity));
+    }
+
+    for (player_entity, command) in commands {
+      let chest_entity = match chests.iter().find(|(id, _)| *id == command.get_chest()) {
+        Some((_, entity)) => *entity,
+        None => continue,
+      };
+
+      if !is_in_reach(scene, player_entity, chest_entity) {
+        log::warn!("rejected chest command {:?}: not at the chest", command);
+        continue;
+      }
+
+      let mut chest = match scene.get_components_mut::<&Chest>(chest_entity) {
+        Some(chest) => chest.clone(),
+        None => continue,
+      };
+
+      let result = match scene.get_components_mut::<&mut Character>(player_entity) {
+        Some(character) => chest.apply(character, &command),
+        None => continue,
+      };
+
+      match result {
+        Ok(()) => {
+          if let Some(data) = scene.get_components_mut::<&mut Chest>(chest_entity) {
+            *data = chest;
+          }
+        }
+        Err(error) => log::warn!("rejected chest command {:?}: {:}", command, error),
+      }
+    }
+  }
+
+  /// Opens the chest window once the player uses the chest they target, and
+  /// keeps its contents up to date as anyone changes them.
+  #[cfg(target_arch = "wasm32")]
+  pub fn handle_chest_window(&mut self, scene: &mut Scene) {
+    let mut nearby = get_used_target(scene, "OpenChest");
+    let player = get_local_player(scene);
+    if nearby.is_none()
+      && let Some((open_entity, _)) = &self.open
+      && let Some(player_entity) = player
+      && is_in_reach(scene, player_entity, *open_entity)
+    {
+      nearby = Some(*open_entity);
+    }
+
+    let chest = match nearby {
+      Some(entity) => match scene.get_components_mut::<(&IdComponent, &Chest)>(entity) {
+        Some((id, chest)) => Some((entity, **id, chest.inventory.clone())),
+        None => None,
+      },
+      None => None,
+    };
+
+    match chest {
+      Some((entity, id, slots)) => {
+        let is_current = match &self.open {
+          Some((open_entity, open_slots)) => *open_entity == entity && *open_slots == slots,
+          None => false,
+        };
+
+        if !is_current {
+          self.browser.send(Message::OpenChest {
+            chest: id,
+            slots: slots.clone(),
+          });
+          self.open = Some((entity, slots));
+        }
+      }
+      None => {
+        if self.open.take().is_some() {
+          self.browser.send(Message::CloseChest);
+        }
+      }
+    }
+  }
+}
+
+impl System for StorageSystem {
+  fn get_name(&self) -> &'static str {
+    "StorageSystem"
+  }
+
+  fn run(&mut self, scene: &mut Scene, _: &mut Backpack) {
+    #[cfg(not(target_arch = "wasm32"))]
+    self.handle_commands(scene);
+    #[cfg(target_arch = "wasm32")]
+    self.handle_chest_window(scene);
+  }
+}
@@ -6,15 +6,106 @@ use engine::{
     scene::{IdComponent, Scene, TransformComponent},
   },
   systems::{Backpack, Initializable, Inventory, System},
+  tsify,
   utils::units::{Framerate, Radians, Seconds},
 };
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
 
-pub struct TimeOfDaySystem {}
+#[cfg(target_arch = "wasm32")]
+use crate::client::browser::Message;
+#[cfg(target_arch = "wasm32")]
+use engine::systems::browser::BrowserController;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Serialize, Deserialize, tsify::Tsify)]
+pub enum Season {
+  Spring,
+  Summer,
+  Fall,
+  Winter,
+}
+
+impl Season {
+  pub const ALL: [Season; 4] = [Self::Spring, Self::Summer, Self::Fall, Self::Winter];
+
+  pub fn from_index(index: u32) -> Self {
+    Self::ALL[index as usize % Self::ALL.len()]
+  }
+
+  pub fn get_index(&self) -> u32 {
+    *self as u32
+  }
+}
+
+impl std::fmt::Display for Season {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Spring => write!(f, "Spring"),
+      Self::Summer => write!(f, "Summer"),
+      Self::Fall => write!(f, "Fall"),
+      Self::Winter => write!(f, "Winter"),
+    }
+  }
+}
+
+/// In-game date, counted from the first day of spring of the first year. Days
+/// and years start at 1.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, tsify::Tsify)]
+pub struct Calendar {
+  pub day: u32,
+  pub season: Season,
+  pub year: u32,
+}
+
+impl Calendar {
+  pub fn from_day(day: u32, days_per_season: u32) -> Self {
+    let days_per_season = days_per_season.max(1);
+    let seasons = day / days_per_season;
+    let per_year = Season::ALL.len() as u32;
+
+    Self {
+      day: day % days_per_season + 1,
+      season: Season::from_index(seasons),
+      year: seasons / per_year + 1,
+    }
+  }
+
+  /// Seasons gone by since the first spring, which keeps counting up across
+  /// the years
+  pub fn get_season_count(&self) -> u32 {
+    (self.year - 1) * Season::ALL.len() as u32 + self.season.get_index()
+  }
+}
+
+/// Refreshed every frame by the `TimeOfDaySystem`, so other systems can react
+/// to the start of a new in-game day without keeping track of it themselves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DayCycle {
+  pub day: u32,
+  pub is_new_day: bool,
+  pub calendar: Calendar,
+}
+
+pub struct TimeOfDaySystem {
+  day: Option<u32>,
+  #[cfg(target_arch = "wasm32")]
+  browser: BrowserController<Message>,
+  #[cfg(target_arch = "wasm32")]
+  calendar: Option<Calendar>,
+}
 
 impl Initializable for TimeOfDaySystem {
-  fn initialize(_: &Inventory) -> Self {
-    Self {}
+  #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+  fn initialize(inventory: &Inventory) -> Self {
+    #[cfg(target_arch = "wasm32")]
+    let browser = inventory.get::<BrowserController<Message>>().clone();
+    Self {
+      day: None,
+      #[cfg(target_arch = "wasm32")]
+      browser,
+      #[cfg(target_arch = "wasm32")]
+      calendar: None,
+    }
   }
 }
 
@@ -27,9 +118,12 @@ impl TimeOfDaySystem {
     if let Some((_, (time_of_day, text))) =
       scene.query_one::<(&mut TimeOfDay, &mut TextComponent)>()
     {
-      time_of_day.current_time = (time_of_day.current_time
-        + time_of_day.delta_time * *Seconds::from(Framerate::new(60.0)))
-        % time_of_day.total_time;
+      let current_time =
+        time_of_day.current_time + time_of_day.delta_time * *Seconds::from(Framerate::new(60.0));
+      if current_time >= time_of_day.total_time {
+        time_of_day.day += 1;
+      }
+      time_of_day.current_time = current_time % time_of_day.total_time;
 
       let hour = time_of_day.get_hours();
       let minute = time_of_day.get_minutes();
@@ -56,6 +150,38 @@ impl TimeOfDaySystem {
     }
   }
 
+  pub fn handle_day_cycle(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let (day, calendar) = match scene.query_one::<&TimeOfDay>() {
+      Some((_, time_of_day)) => (time_of_day.day, time_of_day.get_calendar()),
+      None => return,
+    };
+
+    let is_new_day = match self.day {
+      Some(previous) => previous != day,
+      None => false,
+    };
+    self.day = Some(day);
+
+    backpack.insert(DayCycle {
+      day,
+      is_new_day,
+      calendar,
+    });
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  pub fn handle_calendar(&mut self, backpack: &mut Backpack) {
+    let calendar = match backpack.get::<DayCycle>() {
+      Some(cycle) => cycle.calendar,
+      None => return,
+    };
+
+    if self.calendar != Some(calendar) {
+      self.browser.send(Message::UpdateCalendar { calendar });
+      self.calendar = Some(calendar);
+    }
+  }
+
   pub fn friends_map(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
     let mut friends = backpack
       .entry::<Friends>()
@@ -76,6 +202,9 @@ impl System for TimeOfDaySystem {
 
   fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
     self.position_sun(scene);
+    self.handle_day_cycle(scene, backpack);
+    #[cfg(target_arch = "wasm32")]
+    self.handle_calendar(backpack);
     self.friends_map(scene, backpack);
   }
 }
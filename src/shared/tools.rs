@@ -0,0 +1,283 @@
+use crate::shared::components::{
+  Character, Durability, FarmWork, RepairStation, ToolError, ToolType, WaterCan,
+};
+use crate::shared::farming::{is_in_reach, FarmAction};
+use crate::shared::game_input::GameInput;
+use engine::{
+  application::scene::{IdComponent, Scene},
+  nalgebra::Vector3,
+  systems::{Backpack, Initializable, Inventory, System},
+  utils::units::Seconds,
+  Entity,
+};
+
+#[cfg(target_arch = "wasm32")]
+use crate::client::browser::Message;
+#[cfg(target_arch = "wasm32")]
+use crate::shared::components::CanTier;
+#[cfg(target_arch = "wasm32")]
+use crate::shared::farming::{get_local_player, get_used_target};
+#[cfg(target_arch = "wasm32")]
+use crate::shared::game_input::InputState;
+#[cfg(target_arch = "wasm32")]
+use engine::{application::components::SelfComponent, systems::browser::BrowserController};
+
+/// Wears down `tool` when the character keeps track of its durability, and
+/// refuses the action when the tool is broken.
+pub fn use_tool(durability: Option<&mut Durability>, tool: ToolType) -> Result<(), ToolError> {
+  match durability {
+    Some(durability) => durability.use_tool(tool),
+    None => Ok(()),
+  }
+}
+
+/// Repairs the damaged tools of the player at a repair station, paid for with
+/// the materials the station takes or with cash. Using the station also opens
+/// its window, where the watering can is upgraded.
+#[derive(Default)]
+pub struct Repair {}
+
+impl FarmAction for Repair {
+  fn name(&self) -> &'static str {
+    "Repair"
+  }
+
+  fn get_label(&self) -> &'static str {
+    "Repair Tools"
+  }
+
+  fn get_progress_label(&self, _: &FarmWork) -> String {
+    String::from("Repairing..")
+  }
+
+  fn is_selectable(&self) -> bool {
+    false
+  }
+
+  fn get_color(&self) -> Vector3<f32> {
+    Vector3::new(0.5, 0.5, 1.0)
+  }
+
+  fn get_candidates(&self, scene: &mut Scene, _: Entity) -> Vec<Entity> {
+    let mut stations = vec![];
+    for (entity, _) in scene.query_mut::<&RepairStation>() {
+      stations.push(entity);
+    }
+    stations
+  }
+
+  fn start(
+    &self,
+    scene: &mut Scene,
+    _: &mut Backpack,
+    player: Entity,
+    target: Entity,
+  ) -> Option<FarmWork> {
+    let durability = scene.get_components_mut::<&Durability>(player)?;
+    if durability.get_damaged().is_empty() {
+      return None;
+    }
+
+    Some(FarmWork::new(self.name(), target, Seconds::new(0.5)))
+  }
+
+  fn complete(&self, scene: &mut Scene, _: &mut Backpack, player: Entity, work: FarmWork) {
+    let station = match work
+      .get_target()
+      .and_then(|station_entity| scene.get_components_mut::<&RepairStation>(station_entity))
+    {
+      Some(station) => station.clone(),
+      None => return,
+    };
+
+    let (character, durability) =
+      match scene.get_components_mut::<(&mut Character, &mut Durability)>(player) {
+        Some(components) => components,
+        None => return,
+      };
+
+    for tool in durability.get_damaged() {
+      let has_materials = !station.materials.is_empty()
+        && station
+          .materials
+          .iter()
+          .all(|material| character.has_at_least(&material.item, material.quantity));
+
+      if has_materials {
+        if let Err(error) = character.remove_all(&station.materials) {
+          log::warn!(
+            "can't take the materials to repair the {:}: {:}",
+            tool,
+            error
+          );
+          break;
+        }
+      } else if character.cash >= station.cash {
+        character.cash -= station.cash;
+      } else {
+        log::warn!("can't afford to repair the {:}", tool);
+        break;
+      }
+
+      durability.repair(&tool);
+    }
+  }
+}
+
+pub struct ToolsSystem {
+  #[cfg(target_arch = "wasm32")]
+  browser: BrowserController<Message>,
+  #[cfg(target_arch = "wasm32")]
+  reported: Option<ToolType>,
+  // Repair station whose window is open, along with the can tier it shows
+  #[cfg(target_arch = "wasm32")]
+  open: Option<(Entity, CanTier)>,
+}
+
+impl Initializable for ToolsSystem {
+  #[cfg_attr(not(target_arch = "wasm32"), allow(unused_variables))]
+  fn initialize(inventory: &Inventory) -> Self {
+    #[cfg(target_arch = "wasm32")]
+    let browser = inventory.get::<BrowserController<Message>>().clone();
+    Self {
+      #[cfg(target_arch = "wasm32")]
+      browser,
+      #[cfg(target_arch = "wasm32")]
+      reported: None,
+      #[cfg(target_arch = "wasm32")]
+      open: None,
+    }
+  }
+}
+
+impl ToolsSystem {
+  /// Upgrades the watering can of players who asked for it at the repair
+  /// station they are standing at, once for every request
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn handle_can_upgrades(&self, scene: &mut Scene) {
+    let mut requests = vec![];
+    for (entity, input) in scene.query_mut::<&GameInput>() {
+      for station in &input.upgrades {
+        requests.push((entity, *station));
+      }
+    }
+
+    let mut stations = vec![];
+    for (entity, (id, station)) in scene.query_mut::<(&IdComponent, &RepairStation)>() {
+      stations.push((**id, entity, station.clone()));
+    }
+
+    for (player_entity, station_id) in requests {
+      let (station_entity, station) = match stations.iter().find(|(id, _, _)| *id == station_id) {
+        Some((_, entity, station)) => (*entity, station),
+        None => continue,
+      };
+
+      if !is_in_reach(scene, player_entity, station_entity) {
+        log::warn!("rejected watering can upgrade: not at the repair station");
+        continue;
+      }
+
+      let (character, can) =
+        match scene.get_components_mut::<(&mut Character, &mut WaterCan)>(player_entity) {
+          Some(components) => components,
+          None => continue,
+        };
+
+      let upgrade = match station.get_upgrade(can.tier) {
+        Some(upgrade) => upgrade,
+        None => continue,
+      };
+
+      if character.cash < upgrade.cash {
+        log::warn!("can't afford to upgrade the {:} watering can", can.tier);
+        continue;
+      }
+      if let Err(error) = character.remove_all(&upgrade.materials) {
+        log::warn!("can't upgrade the {:} watering can: {:}", can.tier, error);
+        continue;
+      }
+
+      character.cash -= upgrade.cash;
+      can.upgrade();
+    }
+  }
+
+  /// Opens the repair station window once the player uses the station they
+  /// target, showing what upgrading their watering can would cost, and closes
+  /// it once they walk away
+  #[cfg(target_arch = "wasm32")]
+  pub fn handle_station_window(&mut self, scene: &mut Scene) {
+    let mut station = get_used_target(scene, "Repair");
+    let player = get_local_player(scene);
+    if station.is_none()
+      && let Some((open_entity, _)) = self.open
+      && let Some(player_entity) = player
+      && is_in_reach(scene, player_entity, open_entity)
+    {
+      station = Some(open_entity);
+    }
+
+    let tier = scene
+      .query_one::<(&WaterCan, &SelfComponent)>()
+      .map(|(_, (can, _))| can.tier);
+    let nearby = station.zip(tier);
+
+    match nearby {
+      Some(open) if self.open != Some(open) => {
+        let (entity, tier) = open;
+        if let Some((id, station)) =
+          scene.get_components_mut::<(&IdComponent, &RepairStation)>(entity)
+        {
+          self.browser.send(Message::OpenRepairStation {
+            station: **id,
+            tier,
+            upgrade: station.get_upgrade(tier).cloned(),
+          });
+          self.open = Some(open);
+        }
+      }
+      Some(_) => {}
+      None => {
+        if self.open.take().is_some() {
+          self.browser.send(Message::CloseRepairStation);
+        }
+      }
+    }
+  }
+
+  /// Lets the player know when a broken tool refuses its action, once for
+  /// every time they try it.
+  #[cfg(target_arch = "wasm32")]
+  pub fn handle_feedback(&mut self, scene: &mut Scene) {
+    for (_, (input, durability, _)) in
+      scene.query_mut::<(&GameInput, &mut Durability, &SelfComponent)>()
+    {
+      if let Some(tool) = durability.refused.take()
+        && self.reported != Some(tool)
+      {
+        self.browser.send(Message::ToolBroken { tool });
+        self.reported = Some(tool);
+      }
+
+      if !input.check(InputState::Action) {
+        self.reported = None;
+      }
+    }
+  }
+}
+
+impl System for ToolsSystem {
+  fn get_name(&self) -> &'static str {
+    "ToolsSystem"
+  }
+
+  fn run(&mut self, scene: &mut Scene, _: &mut Backpack) {
+    #[cfg(not(target_arch = "wasm32"))]
+    self.handle_can_upgrades(scene);
+    #[cfg(target_arch = "wasm32")]
+    self.handle_station_window(scene);
+    #[cfg(target_arch = "wasm32")]
+    self.handle_feedback(scene);
+  }
+}
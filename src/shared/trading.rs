@@ -0,0 +1,425 @@
+use crate::shared::components::{Character, InventoryError, InventoryItem, Quantity};
+use engine::{
+  application::{components::NetworkedPlayerComponent, scene::Scene},
+  systems::{trusty::MultiplayerController, Backpack, Initializable, Inventory, System},
+  tsify, ConnectionId,
+};
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::shared::game_input::GameInput;
+
+#[cfg(target_arch = "wasm32")]
+use crate::client::browser::Message;
+#[cfg(target_arch = "wasm32")]
+use engine::application::components::SelfComponent;
+#[cfg(target_arch = "wasm32")]
+use engine::systems::browser::BrowserController;
+
+/// Trade requests sent by a player to the server along with their inputs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, tsify::Tsify)]
+pub enum TradeCommand {
+  Propose {
+    partner: ConnectionId,
+  },
+  Offer {
+    items: Vec<InventoryItem>,
+    cash: u64,
+  },
+  Confirm,
+  Cancel,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, tsify::Tsify)]
+pub enum TradeStatus {
+  Open,
+  Completed,
+  Cancelled { reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, tsify::Tsify)]
+pub struct TradeOffer {
+  pub connection_id: ConnectionId,
+  pub items: Vec<InventoryItem>,
+  pub cash: u64,
+  pub confirmed: bool,
+}
+
+impl TradeOffer {
+  pub fn new(connection_id: ConnectionId) -> Self {
+    Self {
+      connection_id,
+      items: vec![],
+      cash: 0,
+      confirmed: false,
+    }
+  }
+
+  fn get_quantities(&self) -> Result<Vec<(&InventoryItem, usize)>, TradeError> {
+    let mut quantities = vec![];
+    for offered in &self.items {
+      match offered.quantity {
+        Quantity::Finite(quantity) => quantities.push((offered, quantity)),
+        Quantity::Empty | Quantity::Infinite => return Err(TradeError::InvalidOffer),
+      }
+    }
+    Ok(quantities)
+  }
+
+  /// Takes the offered cash and items out of `from`
+  fn take(&self, from: &mut Character) -> Result<(), TradeError> {
+    if from.cash < self.cash {
+      return Err(TradeError::NotEnoughCash {
+        requested: self.cash,
+        available: from.cash,
+      });
+    }
+
+    from.cash -= self.cash;
+    for (offered, quantity) in self.get_quantities()? {
+      from.remove(&offered.item, quantity)?;
+    }
+    Ok(())
+  }
+
+  /// Hands the offered cash and items to `to`
+  fn award(&self, to: &mut Character) -> Result<(), TradeError> {
+    to.cash += self.cash;
+    for (offered, quantity) in self.get_quantities()? {
+      to.award(offered.item.clone(), quantity)?;
+    }
+    Ok(())
+  }
+}
+
+/// A trade between two players. It is replicated to every client each time it
+/// changes, and clients only forward the trades their own player is part of.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, tsify::Tsify)]
+pub struct Trade {
+  pub id: u64,
+  pub proposer: TradeOffer,
+  pub partner: TradeOffer,
+  pub status: TradeStatus,
+}
+
+impl Trade {
+  pub fn involves(&self, connection_id: &ConnectionId) -> bool {
+    &self.proposer.connection_id == connection_id || &self.partner.connection_id == connection_id
+  }
+
+  pub fn get_offer_mut(&mut self, connection_id: &ConnectionId) -> Option<&mut TradeOffer> {
+    if &self.proposer.connection_id == connection_id {
+      Some(&mut self.proposer)
+    } else if &self.partner.connection_id == connection_id {
+      Some(&mut self.partner)
+    } else {
+      None
+    }
+  }
+
+  pub fn is_confirmed(&self) -> bool {
+    self.proposer.confirmed && self.partner.confirmed
+  }
+
+  /// Swaps both offers. Both are taken out first so the slots they free up can
+  /// hold what comes in, and nobody hands on what they were just given. The
+  /// characters are only updated when every part of the exchange succeeds, so a
+  /// failed trade never loses anything.
+  pub fn exchange(
+    &self,
+    proposer: &mut Character,
+    partner: &mut Character,
+  ) -> Result<(), TradeError> {
+    let mut next_proposer = proposer.clone();
+    let mut next_partner = partner.clone();
+
+    self.proposer.take(&mut next_proposer)?;
+    self.partner.take(&mut next_partner)?;
+    self.proposer.award(&mut next_partner)?;
+    self.partner.award(&mut next_proposer)?;
+
+    *proposer = next_proposer;
+    *partner = next_partner;
+    Ok(())
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TradeError {
+  InvalidOffer,
+  NotEnoughCash { requested: u64, available: u64 },
+  Inventory(InventoryError),
+}
+
+impl From<InventoryError> for TradeError {
+  fn from(error: InventoryError) -> Self {
+    Self::Inventory(error)
+  }
+}
+
+impl std::fmt::Display for TradeError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::InvalidOffer => write!(f, "The offer can not be traded"),
+      Self::NotEnoughCash {
+        requested,
+        available,
+      } => write!(
+        f,
+        "Not enough cash, wanted {:} but only {:} available",
+        requested, available
+      ),
+      Self::Inventory(error) => write!(f, "{:}", error),
+    }
+  }
+}
+
+impl std::error::Error for TradeError {}
+
+/// Every open trade, only tracked by the server
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trades {
+  pub trades: Vec<Trade>,
+  next_id: u64,
+}
+
+impl Trades {
+  pub fn is_trading(&self, connection_id: &ConnectionId) -> bool {
+    self
+      .trades
+      .iter()
+      .any(|trade| trade.status == TradeStatus::Open && trade.involves(connection_id))
+  }
+
+  pub fn get_open_mut(&mut self, connection_id: &ConnectionId) -> Option<&mut Trade> {
+    self
+      .trades
+      .iter_mut()
+      .find(|trade| trade.status == TradeStatus::Open && trade.involves(connection_id))
+  }
+
+  pub fn propose(&mut self, proposer: ConnectionId, partner: ConnectionId) -> bool {
+    if proposer == partner || self.is_trading(&proposer) || self.is_trading(&partner) {
+      return false;
+    }
+
+    self.next_id += 1;
+    self.trades.push(Trade {
+      id: self.next_id,
+      proposer: TradeOffer::new(proposer),
+      partner: TradeOffer::new(partner),
+      status: TradeStatus::Open,
+    });
+    true
+  }
+
+  /// Changing an offer withdraws both confirmations, so nobody ends up
+  /// accepting something they have not seen.
+  pub fn offer(&mut self, connection_id: &ConnectionId, items: Vec<InventoryItem>, cash: u64) {
+    if let Some(trade) = self.get_open_mut(connection_id) {
+      trade.proposer.confirmed = false;
+      trade.partner.confirmed = false;
+
+      if let Some(offer) = trade.get_offer_mut(connection_id) {
+        offer.items = items;
+        offer.cash = cash;
+      }
+    }
+  }
+
+  pub fn confirm(&mut self, connection_id: &ConnectionId) {
+    if let Some(trade) = self.get_open_mut(connection_id)
+      && let Some(offer) = trade.get_offer_mut(connection_id)
+    {
+      offer.confirmed = true;
+    }
+  }
+
+  pub fn cancel(&mut self, connection_id: &ConnectionId, reason: &str) {
+    if let Some(trade) = self.get_open_mut(connection_id) {
+      trade.status = TradeStatus::Cancelled {
+        reason: reason.to_string(),
+      };
+    }
+  }
+}
+
+pub struct TradingSystem {
+  multiplayer: MultiplayerController,
+  #[cfg(target_arch = "wasm32")]
+  browser: BrowserController<Message>,
+  #[cfg(not(target_arch = "wasm32"))]
+  previous: Vec<Trade>,
+}
+
+impl Initializable for TradingSystem {
+  fn initialize(inventory: &Inventory) -> Self {
+    let multiplayer = inventory.get::<MultiplayerController>().clone();
+    #[cfg(target_arch = "wasm32")]
+    let browser = inventory.get::<BrowserController<Message>>().clone();
+    Self {
+      multiplayer,
+      #[cfg(target_arch = "wasm32")]
+      browser,
+      #[cfg(not(target_arch = "wasm32"))]
+      previous: vec![],
+    }
+  }
+}
+
+impl TradingSystem {
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn handle_commands(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let mut commands = vec![];
+    let mut players = vec![];
+    for (_, (input, network, character)) in
+      scene.query_mut::<(&GameInput, &NetworkedPlayerComponent, &Character)>()
+    {
+      players.push(network.connection_id);
+      for command in &input.trades {
+        commands.push((network.connection_id, character.clone(), command.clone()));
+      }
+    }
+
+    let trades = match backpack.get_mut::<Trades>() {
+      Some(trades) => trades,
+      None => return,
+    };
+
+    for (connection_id, character, command) in commands {
+      match command {
+        TradeCommand::Propose { partner } => {
+          if !players.contains(&partner) || !trades.propose(connection_id, partner) {
+            log::warn!("rejected trade proposal from {:?}", connection_id);
+          }
+        }
+        TradeCommand::Offer { items, cash } => {
+          // Offers are checked again when the trade completes, but rejecting
+          // them early keeps the other player from seeing what can't be traded
+          let is_available = character.cash >= cash
+            && items.iter().all(|offered| match offered.quantity {
+              Quantity::Finite(quantity) => character.has_at_least(&offered.item, quantity),
+              Quantity::Empty | Quantity::Infinite => false,
+            });
+
+          if is_available {
+            trades.offer(&connection_id, items, cash);
+          } else {
+            log::warn!("rejected trade offer from {:?}", connection_id);
+          }
+        }
+        TradeCommand::Confirm => trades.confirm(&connection_id),
+        TradeCommand::Cancel => trades.cancel(&connection_id, "The trade was cancelled"),
+      }
+    }
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn handle_exchange(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    let trades = match backpack.get_mut::<Trades>() {
+      Some(trades) => trades,
+      None => return,
+    };
+
+    for trade in trades.trades.iter_mut() {
+      if trade.status != TradeStatus::Open || !trade.is_confirmed() {
+        continue;
+      }
+
+      let mut proposer = None;
+      let mut partner = None;
+      for (entity, (network, character)) in
+        scene.query_mut::<(&NetworkedPlayerComponent, &Character)>()
+      {
+        if network.connection_id == trade.proposer.connection_id {
+          proposer = Some((entity, character.clone()));
+        } else if network.connection_id == trade.partner.connection_id {
+          partner = Some((entity, character.clone()));
+        }
+      }
+
+      let (Some((proposer_entity, mut proposer)), Some((partner_entity, mut partner))) =
+        (proposer, partner)
+      else {
+        trade.status = TradeStatus::Cancelled {
+          reason: String::from("The other player is gone"),
+        };
+        continue;
+      };
+
+      match trade.exchange(&mut proposer, &mut partner) {
+        Ok(()) => {
+          if let Some(character) = scene.get_components_mut::<&mut Character>(proposer_entity) {
+            *character = proposer;
+          }
+          if let Some(character) = scene.get_components_mut::<&mut Character>(partner_entity) {
+            *character = partner;
+          }
+          trade.status = TradeStatus::Completed;
+        }
+        Err(error) => {
+          trade.status = TradeStatus::Cancelled {
+            reason: error.to_string(),
+          };
+        }
+      }
+    }
+  }
+
+  /// Sends every trade that changed since the last frame, and forgets about the
+  /// ones that are closed once everybody has been told.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn handle_replicate(&mut self, backpack: &mut Backpack) {
+    let trades = match backpack.get_mut::<Trades>() {
+      Some(trades) => trades,
+      None => return,
+    };
+
+    for trade in &trades.trades {
+      if !self.previous.contains(trade) {
+        self.multiplayer.broadcast_custom(trade.clone());
+      }
+    }
+
+    trades
+      .trades
+      .retain(|trade| trade.status == TradeStatus::Open);
+    self.previous = trades.trades.clone();
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  pub fn handle_receive_trades(&mut self, scene: &mut Scene) {
+    let connection_id = match scene.query_one::<(&NetworkedPlayerComponent, &SelfComponent)>() {
+      Some((_, (network, _))) => network.connection_id,
+      None => return,
+    };
+
+    while let Ok(trade) = self.multiplayer.try_recv_custom::<Trade>() {
+      if trade.involves(&connection_id) {
+        self.browser.send(Message::UpdateTrade { trade });
+      }
+    }
+  }
+}
+
+impl System for TradingSystem {
+  fn get_name(&self) -> &'static str {
+    "TradingSystem"
+  }
+
+  fn attach(&mut self, _scene: &mut Scene, backpack: &mut Backpack) {
+    backpack.insert(Trades::default());
+  }
+
+  fn run(&mut self, scene: &mut Scene, backpack: &mut Backpack) {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+      self.handle_commands(scene, backpack);
+      self.handle_exchange(scene, backpack);
+      self.handle_replicate(backpack);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    self.handle_receive_trades(scene);
+  }
+}
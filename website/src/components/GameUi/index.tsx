@@ -202,7 +202,7 @@ const GameUi: React.FC<GameUiProps> = () => {
               if (!inventory) {
                 return <StorageBucket />;
               } else if (inventory?.item === "Nothing") {
-              } else if ("Crop" in inventory.item && inventory?.item?.Crop === "Pumpkin") {
+              } else if ("Crop" in inventory.item && inventory?.item?.Crop[0] === "Pumpkin") {
                 item = PumpkinCrop;
               } else if ("Seed" in inventory.item && inventory?.item?.Seed === "Pumpkin") {
                 item = PumpkinSeeds;